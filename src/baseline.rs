@@ -0,0 +1,146 @@
+use crate::diagnostic::Diagnostic;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+const VERSION: u32 = 1;
+
+/// Previously accepted findings, so that only newly introduced ones are reported.
+///
+/// Findings are identified by a fingerprint of their rule, file and message, which keeps
+/// them matched when unrelated edits move them to another line. Each fingerprint stores how
+/// often it occurred, so a second copy of a baselined finding is still reported. Paths are
+/// taken relative to the directory of the baseline file, so a finding keeps its fingerprint
+/// however the file was named on the command line.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    version: u32,
+    fingerprints: BTreeMap<String, usize>,
+    /// Directory of the baseline file, which paths are made relative to
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+impl Baseline {
+    /// Creates an empty baseline to be saved at `path`.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            version: VERSION,
+            fingerprints: BTreeMap::new(),
+            root: directory_of(path),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline: {}", path.display()))?;
+        let mut baseline: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid baseline file: {}", path.display()))?;
+        if baseline.version != VERSION {
+            anyhow::bail!(
+                "Unsupported baseline version {} in {} (expected {VERSION})",
+                baseline.version,
+                path.display()
+            );
+        }
+        baseline.root = directory_of(path);
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write baseline: {}", path.display()))
+    }
+
+    /// Adds the diagnostics of `path` to the baseline.
+    pub fn record(&mut self, path: &Path, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            *self
+                .fingerprints
+                .entry(fingerprint(&self.relative(path), diagnostic))
+                .or_default() += 1;
+        }
+    }
+
+    /// Removes the diagnostics of `path` that are covered by the baseline.
+    pub fn filter(&self, path: &Path, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let mut remaining: HashMap<String, usize> = HashMap::new();
+
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let fingerprint = fingerprint(&self.relative(path), diagnostic);
+                let count = remaining
+                    .entry(fingerprint)
+                    .or_insert_with_key(|key| self.fingerprints.get(key).copied().unwrap_or(0));
+                if *count > 0 {
+                    *count -= 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fingerprints.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fingerprints.is_empty()
+    }
+
+    /// `path` relative to the baseline's directory, or absolute if it lies outside it.
+    fn relative(&self, path: &Path) -> PathBuf {
+        let path = normalize(path);
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => path,
+        }
+    }
+}
+
+fn directory_of(path: &Path) -> PathBuf {
+    normalize(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Makes `path` absolute and removes its `.` and `..` components, without resolving
+/// symlinks, so that paths naming the same file the same way compare equal.
+fn normalize(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// 64-bit FNV-1a hash of the rule id, path and message, which is stable across platforms
+/// and Rust versions unlike `std`'s hasher.
+fn fingerprint(path: &Path, diagnostic: &Diagnostic) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for part in [diagnostic.rule_id.as_str(), &path, &diagnostic.message] {
+        for byte in part.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    format!("{hash:016x}")
+}
@@ -0,0 +1,83 @@
+use crate::config::Config;
+use crate::diagnostic::Diagnostic;
+use crate::editorconfig::EditorConfig;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// On-disk cache of the diagnostics produced for each file.
+///
+/// Entries are stored per absolute path and are only used when the file content, the rumk
+/// version, the configuration and the file's `.editorconfig` settings all match the run
+/// that wrote them. The cache is best effort: unreadable or unwritable entries behave like
+/// misses.
+pub struct Cache {
+    dir: PathBuf,
+    settings: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Cache {
+    /// Opens the cache in `RUMK_CACHE_DIR`, or else the platform's user cache directory.
+    pub fn open(config: &Config) -> Option<Self> {
+        let dir = match std::env::var_os("RUMK_CACHE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => ProjectDirs::from("", "", "rumk")?.cache_dir().to_path_buf(),
+        };
+        Some(Self::new(dir, config))
+    }
+
+    pub fn new(dir: PathBuf, config: &Config) -> Self {
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        config.source.hash(&mut hasher);
+        config.editorconfig.hash(&mut hasher);
+        for rule in &config.rules {
+            rule.id().hash(&mut hasher);
+        }
+
+        Self {
+            dir,
+            settings: hasher.finish(),
+        }
+    }
+
+    pub fn get(&self, path: &Path, content: &str) -> Option<Vec<Diagnostic>> {
+        let entry = std::fs::read_to_string(self.entry_path(path)).ok()?;
+        let entry: Entry = serde_json::from_str(&entry).ok()?;
+        (entry.key == self.key(path, content)).then_some(entry.diagnostics)
+    }
+
+    pub fn put(&self, path: &Path, content: &str, diagnostics: &[Diagnostic]) {
+        let entry = Entry {
+            key: self.key(path, content),
+            diagnostics: diagnostics.to_vec(),
+        };
+        if let Ok(json) = serde_json::to_string(&entry) {
+            let _ = std::fs::create_dir_all(&self.dir);
+            let _ = std::fs::write(self.entry_path(path), json);
+        }
+    }
+
+    fn key(&self, path: &Path, content: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.settings.hash(&mut hasher);
+        EditorConfig::for_file(path).hash(&mut hasher);
+        content.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut hasher = DefaultHasher::new();
+        absolute.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+}
@@ -1,20 +1,184 @@
-use crate::rules::{self, Rule};
+use crate::diagnostic::Severity;
+use crate::editorconfig::EditorConfig;
+use crate::rules::{self, Rule, RuleCategory};
 use anyhow::{Context, Result};
+use colored::Color;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 pub struct Config {
     pub rules: Vec<Box<dyn Rule>>,
+    /// Content of the config file the rules were loaded from
+    pub source: Option<String>,
+    pub files: FilePatterns,
+    /// Rule sets from `[[overrides]]` entries, for files matching their `path` glob
+    pub overrides: Vec<Override>,
+    /// Minimum severity that makes `check` exit with a failing status
+    pub fail_level: Option<Severity>,
+    /// Settings from the `.editorconfig` files that apply to a Makefile in the working
+    /// directory, used as defaults for the options of `rules`
+    pub editorconfig: EditorConfig,
+    /// Options set for each rule by the config file or `configure_rule`, which take
+    /// precedence over `.editorconfig` settings
+    pub options: HashMap<String, HashMap<String, toml::Value>>,
+    /// Problems in the config file that didn't prevent loading it
+    pub warnings: Vec<String>,
+    /// Colors and markers of the text output, from the `[output]` section
+    pub output: OutputStyle,
+    /// Read the files each Makefile includes, so rules see their definitions too
+    pub follow_includes: bool,
+}
+
+/// How the text output marks up diagnostics.
+#[derive(Debug, Clone)]
+pub struct OutputStyle {
+    pub error_color: Color,
+    pub warning_color: Color,
+    pub info_color: Color,
+    /// Appended to the messages of diagnostics with a fix
+    pub fix_indicator: String,
+}
+
+impl OutputStyle {
+    pub fn severity_color(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::Error => self.error_color,
+            Severity::Warning => self.warning_color,
+            Severity::Info => self.info_color,
+        }
+    }
+}
+
+impl Default for OutputStyle {
+    fn default() -> Self {
+        Self {
+            error_color: Color::Red,
+            warning_color: Color::Yellow,
+            info_color: Color::Cyan,
+            fix_indicator: "[*]".to_string(),
+        }
+    }
+}
+
+/// An `[[overrides]]` entry: the rules in effect for files matching `path`.
+pub struct Override {
+    path: GlobSet,
+    pub rules: Vec<Box<dyn Rule>>,
+    /// Options set for each rule by the entry, the top-level config or `configure_rule`
+    options: HashMap<String, HashMap<String, toml::Value>>,
+}
+
+/// The rules in effect for a file: a configured rule set, with the rules whose options the
+/// file's `.editorconfig` settings change replaced.
+pub struct RuleSet<'a> {
+    rules: &'a [Box<dyn Rule>],
+    replaced: Vec<Box<dyn Rule>>,
+}
+
+impl RuleSet<'_> {
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().map(|rule| {
+            self.replaced
+                .iter()
+                .find(|replaced| replaced.id() == rule.id())
+                .unwrap_or(rule)
+                .as_ref()
+        })
+    }
+}
+
+/// Glob patterns from the `[files]` section that adjust which files are checked when
+/// walking a directory.
+#[derive(Debug, Clone, Default)]
+pub struct FilePatterns {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl FilePatterns {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: glob_set(include)?,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    /// Whether `path`, relative to the checked directory, matches an `include` pattern.
+    pub fn is_included(&self, path: &Path) -> bool {
+        matches_path_or_name(&self.include, path)
+    }
+
+    /// Whether `path`, relative to the checked directory, matches an `exclude` pattern.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        matches_path_or_name(&self.exclude, path)
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder
+            .add(Glob::new(pattern).with_context(|| format!("Invalid file pattern: {pattern}"))?);
+    }
+    Ok(builder.build()?)
+}
+
+fn matches_path_or_name(set: &GlobSet, path: &Path) -> bool {
+    set.is_match(path) || path.file_name().is_some_and(|name| set.is_match(name))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilesConfig {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleConfig {
     pub enabled: bool,
     pub severity: Option<String>,
+    #[serde(default)]
     pub options: HashMap<String, toml::Value>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideConfig {
+    pub path: String,
+    #[serde(default)]
+    pub rules: HashMap<String, RuleConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutputConfig {
+    pub error_color: Option<String>,
+    pub warning_color: Option<String>,
+    pub info_color: Option<String>,
+    pub fix_indicator: Option<String>,
+}
+
+impl OutputConfig {
+    fn into_style(self) -> Result<OutputStyle> {
+        let defaults = OutputStyle::default();
+        let color = |key: &str, value: Option<String>, default: Color| match value {
+            Some(name) => name
+                .parse()
+                .map_err(|()| anyhow::anyhow!("output.{key}: unknown color '{name}'")),
+            None => Ok(default),
+        };
+
+        Ok(OutputStyle {
+            error_color: color("error_color", self.error_color, defaults.error_color)?,
+            warning_color: color("warning_color", self.warning_color, defaults.warning_color)?,
+            info_color: color("info_color", self.info_color, defaults.info_color)?,
+            fix_indicator: self.fix_indicator.unwrap_or(defaults.fix_indicator),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct IgnoreConfig {
     pub paths: Vec<String>,
@@ -25,6 +189,15 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             rules: rules::get_default_rules(),
+            source: None,
+            files: FilePatterns::default(),
+            overrides: Vec::new(),
+            fail_level: None,
+            editorconfig: EditorConfig::default(),
+            options: HashMap::new(),
+            warnings: Vec::new(),
+            output: OutputStyle::default(),
+            follow_includes: false,
         }
     }
 }
@@ -37,52 +210,361 @@ impl Config {
         let toml_config: TomlConfig = toml::from_str(&content)
             .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
 
-        Ok(toml_config.into_config())
+        let mut config = toml_config
+            .into_config(discover_editorconfig())
+            .with_context(|| format!("Invalid config file: {}", path.display()))?;
+        config.source = Some(content);
+        for warning in &mut config.warnings {
+            *warning = format!("{}: {warning}", path.display());
+        }
+        Ok(config)
     }
 
-    pub fn find_and_load() -> Result<Self> {
-        let possible_paths = [
-            PathBuf::from(".rumk.toml"),
-            PathBuf::from("rumk.toml"),
-            PathBuf::from(".config/rumk.toml"),
-        ];
-
-        for path in &possible_paths {
-            if path.exists() {
-                return Self::from_file(path);
+    /// Returns the rules in effect for `path`, taken from the last `[[overrides]]` entry
+    /// whose glob matches it, or the top-level rules if none does, and configured with the
+    /// `.editorconfig` settings for `path`. Globs are matched against the path relative to
+    /// the working directory.
+    pub fn rules_for(&self, path: &Path) -> RuleSet<'_> {
+        let stripped = path.strip_prefix(".").unwrap_or(path);
+        let relative = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| stripped.strip_prefix(cwd).ok())
+            .unwrap_or(stripped);
+
+        let (rules, options) = self
+            .overrides
+            .iter()
+            .rev()
+            .find(|entry| entry.path.is_match(relative))
+            .map_or((&self.rules, &self.options), |entry| {
+                (&entry.rules, &entry.options)
+            });
+
+        let editorconfig = EditorConfig::for_file(path);
+        if editorconfig == self.editorconfig {
+            return RuleSet {
+                rules,
+                replaced: Vec::new(),
+            };
+        }
+
+        // Rules configured from either set of settings are rebuilt, with the config's own
+        // options applied last so they still take precedence
+        let defaults = editorconfig.rule_options();
+        let changed: Vec<&str> = self
+            .editorconfig
+            .rule_options()
+            .into_keys()
+            .chain(defaults.keys().copied())
+            .collect();
+        let replaced = rules
+            .iter()
+            .filter(|rule| changed.contains(&rule.id()))
+            .filter_map(|rule| {
+                let mut replacement = rules::get_all_rules()
+                    .into_iter()
+                    .find(|candidate| candidate.id() == rule.id())?;
+                for options in [defaults.get(rule.id()), options.get(rule.id())]
+                    .into_iter()
+                    .flatten()
+                {
+                    replacement.configure(options).ok()?;
+                }
+                Some(replacement)
+            })
+            .collect();
+
+        RuleSet { rules, replaced }
+    }
+
+    /// Whether checking a file reads other files, whose changes the results cache doesn't
+    /// see: included files with `follow_includes` or MK227's `follow_includes` option, and
+    /// prerequisites with MK216's `check_fs` option.
+    pub fn reads_other_files(&self) -> bool {
+        let enabled = |options: &HashMap<String, HashMap<String, toml::Value>>, id, key| {
+            options
+                .get(id)
+                .and_then(|options| options.get(key))
+                .and_then(toml::Value::as_bool)
+                .unwrap_or(false)
+        };
+        let overrides = self.overrides.iter().map(|entry| &entry.options);
+
+        self.follow_includes
+            || std::iter::once(&self.options)
+                .chain(overrides)
+                .any(|options| {
+                    enabled(options, "MK227", "follow_includes")
+                        || enabled(options, "MK216", "check_fs")
+                })
+    }
+
+    /// Applies `options` to rule `id` in the top-level rules and in every override.
+    pub fn configure_rule(
+        &mut self,
+        id: &str,
+        options: &HashMap<String, toml::Value>,
+    ) -> Result<()> {
+        let overrides = self.overrides.iter_mut().flat_map(|entry| &mut entry.rules);
+        for rule in self.rules.iter_mut().chain(overrides) {
+            if rule.id() == id {
+                rule.configure(options)?;
+            }
+        }
+
+        let recorded = self.overrides.iter_mut().map(|entry| &mut entry.options);
+        for recorded in std::iter::once(&mut self.options).chain(recorded) {
+            recorded
+                .entry(id.to_string())
+                .or_default()
+                .extend(options.clone());
+        }
+        Ok(())
+    }
+
+    /// Checks the config file at `path` without building a config from it, returning a
+    /// description of each problem found: unknown rule ids, invalid severities, unknown
+    /// option keys and invalid option values. Unreadable or malformed files are errors.
+    pub fn validate_file(path: &Path) -> Result<Vec<String>> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+        let toml_config: TomlConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+        let mut problems = validate_rules(&toml_config.rules, "rules");
+        for (index, entry) in toml_config.overrides.iter().enumerate() {
+            problems.extend(validate_rules(
+                &entry.rules,
+                &format!("overrides[{index}].rules"),
+            ));
+        }
+
+        // Option values are checked by the rules themselves while building the config
+        if problems.is_empty() {
+            if let Err(error) = toml_config.into_config(EditorConfig::default()) {
+                problems.push(format!("{error:#}"));
             }
         }
 
-        Ok(Self::default())
+        Ok(problems)
+    }
+
+    /// Returns the path of the config file in the working directory, if there is one.
+    pub fn find_path() -> Option<PathBuf> {
+        [".rumk.toml", "rumk.toml", ".config/rumk.toml"]
+            .into_iter()
+            .map(PathBuf::from)
+            .find(|path| path.exists())
+    }
+
+    pub fn find_and_load() -> Result<Self> {
+        if let Some(path) = Self::find_path() {
+            return Self::from_file(&path);
+        }
+
+        let editorconfig = discover_editorconfig();
+        let mut config = Self::default();
+        for (rule_id, options) in editorconfig.rule_options() {
+            for rule in config.rules.iter_mut().filter(|rule| rule.id() == rule_id) {
+                rule.configure(&options)?;
+            }
+        }
+        config.editorconfig = editorconfig;
+        Ok(config)
     }
 }
 
+/// The `.editorconfig` settings for a Makefile in the working directory.
+fn discover_editorconfig() -> EditorConfig {
+    EditorConfig::for_file(Path::new("Makefile"))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TomlConfig {
     #[serde(default)]
     rules: HashMap<String, RuleConfig>,
 
+    /// Whether the rules of a category run unless `[rules]` says otherwise
+    #[serde(default)]
+    categories: HashMap<RuleCategory, bool>,
+
     #[serde(default)]
     ignore: IgnoreConfig,
+
+    #[serde(default)]
+    files: FilesConfig,
+
+    #[serde(default)]
+    overrides: Vec<OverrideConfig>,
+
+    #[serde(default)]
+    output: OutputConfig,
+
+    fail_level: Option<String>,
+
+    /// Treat unknown rule ids as errors rather than warnings
+    #[serde(default)]
+    strict: bool,
 }
 
 impl TomlConfig {
-    fn into_config(self) -> Config {
-        let mut rules = Vec::new();
-        let all_rules = rules::get_all_rules();
+    fn into_config(mut self, editorconfig: EditorConfig) -> Result<Config> {
+        let unknown = self.unknown_rule_ids();
+        if self.strict && !unknown.is_empty() {
+            anyhow::bail!("{}", unknown.join("; "));
+        }
 
-        for rule in all_rules {
-            let rule_id = rule.id();
+        // A category setting turns its rules on or off, opt-in rules included, unless
+        // `[rules]` has an entry for the rule
+        for rule in rules::get_all_rules() {
+            if let Some(&enabled) = self.categories.get(&rule.category()) {
+                self.rules
+                    .entry(rule.id().to_string())
+                    .or_insert_with(|| RuleConfig {
+                        enabled,
+                        severity: None,
+                        options: HashMap::new(),
+                    });
+            }
+        }
 
-            if let Some(config) = self.rules.get(rule_id) {
-                if config.enabled {
-                    rules.push(rule);
-                }
-            } else {
-                rules.push(rule);
+        let options = rule_options(&self.rules);
+
+        // `.editorconfig` settings fill in options the config file doesn't set
+        for (rule_id, options) in editorconfig.rule_options() {
+            let settings = self
+                .rules
+                .entry(rule_id.to_string())
+                .or_insert_with(|| RuleConfig {
+                    enabled: true,
+                    severity: None,
+                    options: HashMap::new(),
+                });
+            for (key, value) in options {
+                settings.options.entry(key).or_insert(value);
             }
         }
 
-        Config { rules }
+        let overrides = self
+            .overrides
+            .iter()
+            .map(|entry| {
+                // An override's rule settings replace the top-level settings of the same rule
+                let mut settings = self.rules.clone();
+                settings.extend(entry.rules.clone());
+
+                let mut options = options.clone();
+                options.extend(rule_options(&entry.rules));
+
+                Ok(Override {
+                    path: glob_set(std::slice::from_ref(&entry.path))?,
+                    rules: build_rules(&settings)
+                        .with_context(|| format!("Invalid override for {}", entry.path))?,
+                    options,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Config {
+            rules: build_rules(&self.rules)?,
+            source: None,
+            files: FilePatterns::new(&self.files.include, &self.files.exclude)?,
+            overrides,
+            fail_level: self
+                .fail_level
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(|error: String| anyhow::anyhow!("fail_level: {error}"))?,
+            editorconfig,
+            options,
+            warnings: unknown,
+            output: self.output.into_style()?,
+            follow_includes: false,
+        })
     }
+
+    /// Describes each `[rules.X]` entry, top-level or in an override, whose id isn't a rule.
+    fn unknown_rule_ids(&self) -> Vec<String> {
+        let known: Vec<&str> = rules::get_all_rules()
+            .iter()
+            .map(|rule| rule.id())
+            .collect();
+        let overrides = self.overrides.iter().flat_map(|entry| entry.rules.keys());
+
+        let mut ids: Vec<&String> = self
+            .rules
+            .keys()
+            .chain(overrides)
+            .filter(|id| !known.contains(&id.as_str()))
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        ids.into_iter()
+            .map(|id| match rules::closest_rule_id(id) {
+                Some(closest) => format!("unknown rule id '{id}' (did you mean {closest}?)"),
+                None => format!("unknown rule id '{id}'"),
+            })
+            .collect()
+    }
+}
+
+fn validate_rules(settings: &HashMap<String, RuleConfig>, table: &str) -> Vec<String> {
+    let all_rules = rules::get_all_rules();
+    let mut problems = Vec::new();
+
+    let mut ids: Vec<&String> = settings.keys().collect();
+    ids.sort();
+    for id in ids {
+        let config = &settings[id];
+        let Some(rule) = all_rules.iter().find(|rule| rule.id() == id) else {
+            problems.push(format!("[{table}.{id}]: unknown rule id '{id}'"));
+            continue;
+        };
+
+        if let Some(Err(error)) = config.severity.as_deref().map(str::parse::<Severity>) {
+            problems.push(format!("[{table}.{id}]: {error}"));
+        }
+
+        let mut keys: Vec<&String> = config.options.keys().collect();
+        keys.sort();
+        for key in keys {
+            if !rule.options().iter().any(|option| option.name == key) {
+                problems.push(format!("[{table}.{id}]: unknown option '{key}'"));
+            }
+        }
+    }
+
+    problems
+}
+
+fn rule_options(
+    settings: &HashMap<String, RuleConfig>,
+) -> HashMap<String, HashMap<String, toml::Value>> {
+    settings
+        .iter()
+        .map(|(id, config)| (id.clone(), config.options.clone()))
+        .collect()
+}
+
+fn build_rules(settings: &HashMap<String, RuleConfig>) -> Result<Vec<Box<dyn Rule>>> {
+    let mut rules = Vec::new();
+
+    for mut rule in rules::get_all_rules() {
+        let rule_id = rule.id();
+
+        if let Some(config) = settings.get(rule_id) {
+            if config.enabled {
+                rule.configure(&config.options)
+                    .with_context(|| format!("Invalid options for rule {rule_id}"))?;
+                rules.push(rule);
+            }
+        } else if !rule.opt_in() {
+            rules.push(rule);
+        }
+    }
+
+    Ok(rules)
 }
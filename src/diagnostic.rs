@@ -8,7 +8,11 @@ pub struct Diagnostic {
     pub line: usize,
     pub column: usize,
     pub end_line: Option<usize>,
+    /// Exclusive end column of the reported range
     pub end_column: Option<usize>,
+    /// Byte offset of `line`/`column` in the checked content
+    pub offset: Option<usize>,
+    pub end_offset: Option<usize>,
     pub fixable: bool,
     pub fix: Option<Fix>,
 }
@@ -20,10 +24,40 @@ pub enum Severity {
     Info,
 }
 
+impl Severity {
+    /// Whether this severity is `level` or more severe.
+    pub fn is_at_least(self, level: Severity) -> bool {
+        let rank = |severity| match severity {
+            Severity::Info => 0,
+            Severity::Warning => 1,
+            Severity::Error => 2,
+        };
+        rank(self) >= rank(level)
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Severity::Error),
+            "warning" => Ok(Severity::Warning),
+            "info" => Ok(Severity::Info),
+            _ => Err(format!(
+                "invalid severity '{s}', expected \"error\", \"warning\" or \"info\""
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fix {
     pub description: String,
     pub edits: Vec<Edit>,
+    /// Whether applying the fix preserves the Makefile's behavior. Unsafe fixes are only
+    /// applied on request.
+    pub safe: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,11 +85,27 @@ impl Diagnostic {
             column,
             end_line: None,
             end_column: None,
+            offset: None,
+            end_offset: None,
             fixable: false,
             fix: None,
         }
     }
 
+    pub fn with_span(mut self, end_line: usize, end_column: usize) -> Self {
+        self.end_line = Some(end_line);
+        self.end_column = Some(end_column);
+        self
+    }
+
+    /// Sets the byte offsets of the reported range, for rules whose columns are not byte
+    /// columns.
+    pub fn with_offsets(mut self, offset: usize, end_offset: usize) -> Self {
+        self.offset = Some(offset);
+        self.end_offset = Some(end_offset);
+        self
+    }
+
     pub fn with_fix(mut self, fix: Fix) -> Self {
         self.fixable = true;
         self.fix = Some(fix);
@@ -68,6 +118,7 @@ impl Fix {
         Self {
             description: description.into(),
             edits: Vec::new(),
+            safe: true,
         }
     }
 
@@ -75,6 +126,12 @@ impl Fix {
         self.edits.push(edit);
         self
     }
+
+    /// Marks the fix as possibly changing behavior.
+    pub fn as_unsafe(mut self) -> Self {
+        self.safe = false;
+        self
+    }
 }
 
 impl Edit {
@@ -94,3 +151,41 @@ impl Edit {
         }
     }
 }
+
+/// Maps 1-based line/column positions to byte offsets in a file's content.
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(content: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(content.match_indices('\n').map(|(idx, _)| idx + 1));
+
+        Self {
+            line_starts,
+            len: content.len(),
+        }
+    }
+
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        Some((start + column.saturating_sub(1)).min(self.len))
+    }
+
+    /// Fills in `offset` and `end_offset` of each diagnostic from its line/column span,
+    /// unless the rule already set them.
+    pub fn populate(&self, diagnostics: &mut [Diagnostic]) {
+        for diagnostic in diagnostics {
+            if diagnostic.offset.is_some() {
+                continue;
+            }
+            diagnostic.offset = self.offset(diagnostic.line, diagnostic.column);
+            diagnostic.end_offset = match (diagnostic.end_line, diagnostic.end_column) {
+                (Some(line), Some(column)) => self.offset(line, column),
+                _ => None,
+            };
+        }
+    }
+}
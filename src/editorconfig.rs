@@ -0,0 +1,104 @@
+use globset::GlobBuilder;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Properties that `.editorconfig` files set for Makefiles.
+///
+/// Only `max_line_length` is used: `indent_style` can't change what Make accepts, since
+/// recipes must be indented with tabs regardless.
+#[derive(Debug, Default, PartialEq, Hash)]
+pub struct EditorConfig {
+    pub max_line_length: Option<usize>,
+}
+
+impl EditorConfig {
+    /// Reads the properties for the file at `path` from the `.editorconfig` files in its
+    /// directory and their ancestors, up to the first one with `root = true`. Closer files
+    /// take precedence, as do later sections within a file.
+    pub fn for_file(path: &Path) -> Self {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut properties = HashMap::new();
+
+        for dir in path.ancestors().skip(1) {
+            let Ok(content) = std::fs::read_to_string(dir.join(".editorconfig")) else {
+                continue;
+            };
+            let relative = path.strip_prefix(dir).unwrap_or(&path);
+            let (file_properties, root) = parse(&content, relative);
+            for (key, value) in file_properties {
+                properties.entry(key).or_insert(value);
+            }
+            if root {
+                break;
+            }
+        }
+
+        Self {
+            max_line_length: properties
+                .get("max_line_length")
+                .and_then(|value| value.parse().ok()),
+        }
+    }
+
+    /// The options these properties imply for each rule, keyed by rule id.
+    pub fn rule_options(&self) -> HashMap<&'static str, HashMap<String, toml::Value>> {
+        let mut options = HashMap::new();
+        if let Some(max) = self.max_line_length {
+            options.insert(
+                "MK101",
+                HashMap::from([("max".to_string(), toml::Value::Integer(max as i64))]),
+            );
+        }
+        options
+    }
+}
+
+/// Returns the properties of the sections matching the file at `path`, relative to the
+/// `.editorconfig` file, and whether the `.editorconfig` file is marked `root = true`.
+fn parse(content: &str, path: &Path) -> (HashMap<String, String>, bool) {
+    let mut properties = HashMap::new();
+    let mut root = false;
+    // Properties before the first section only apply to `root`
+    let mut in_matching_section = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_matching_section = Some(section_matches(section, path));
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_lowercase();
+        match in_matching_section {
+            None if key == "root" => root = value == "true",
+            Some(true) => {
+                properties.insert(key, value);
+            }
+            _ => {}
+        }
+    }
+
+    (properties, root)
+}
+
+fn section_matches(section: &str, path: &Path) -> bool {
+    // Patterns without a `/` match file names in any directory, others match paths relative
+    // to the `.editorconfig` file
+    let (pattern, subject) = match section.strip_prefix('/') {
+        Some(pattern) => (pattern, path),
+        None if section.contains('/') => (section, path),
+        None => (section, path.file_name().map_or(path, Path::new)),
+    };
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .is_ok_and(|glob| glob.compile_matcher().is_match(subject))
+}
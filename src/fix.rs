@@ -1,26 +1,82 @@
-use crate::diagnostic::Diagnostic;
+use crate::diagnostic::{Diagnostic, Edit};
 
-pub fn apply_fixes(content: &str, diagnostics: &[Diagnostic]) -> String {
+#[derive(Debug, Clone)]
+pub struct FixResult {
+    pub content: String,
+    pub applied: usize,
+    /// Fixes left out because they conflicted with an applied fix
+    pub skipped: usize,
+    /// Unsafe fixes left out because `unsafe_fixes` was false
+    pub unsafe_skipped: usize,
+}
+
+/// Applies the fixes of `diagnostics` to `content`. Fixes not marked safe are only applied
+/// when `unsafe_fixes` is true.
+pub fn apply_fixes(content: &str, diagnostics: &[Diagnostic], unsafe_fixes: bool) -> FixResult {
     let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-    let mut fixable_diagnostics: Vec<_> = diagnostics
+    let (mut fixable_diagnostics, unsafe_diagnostics): (Vec<_>, Vec<_>) = diagnostics
         .iter()
         .filter(|d| d.fixable && d.fix.is_some())
-        .collect();
+        .partition(|d| unsafe_fixes || d.fix.as_ref().is_some_and(|fix| fix.safe));
 
     fixable_diagnostics.sort_by(|a, b| b.line.cmp(&a.line).then_with(|| b.column.cmp(&a.column)));
 
+    // Fixes are applied back to front, so ranges already edited are still expressed in
+    // original coordinates. A fix touching any of them would operate on shifted text.
+    let mut applied_edits: Vec<&Edit> = Vec::new();
+    let mut applied = 0;
+    let mut skipped = 0;
+
     for diagnostic in fixable_diagnostics {
         if let Some(fix) = &diagnostic.fix {
-            for edit in &fix.edits {
+            let conflicts = fix
+                .edits
+                .iter()
+                .any(|edit| applied_edits.iter().any(|other| overlaps(edit, other)));
+
+            if conflicts {
+                skipped += 1;
+                continue;
+            }
+
+            let mut edits: Vec<&Edit> = fix.edits.iter().collect();
+            edits.sort_by_key(|edit| std::cmp::Reverse((edit.start_line, edit.start_column)));
+            for edit in edits {
                 apply_edit(&mut lines, edit);
             }
+            applied_edits.extend(&fix.edits);
+            applied += 1;
         }
     }
 
-    lines.join("\n")
+    let line_ending = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut fixed = lines.join(line_ending);
+    if content.ends_with('\n') {
+        fixed.push_str(line_ending);
+    }
+
+    FixResult {
+        content: fixed,
+        applied,
+        skipped,
+        unsafe_skipped: unsafe_diagnostics.len(),
+    }
+}
+
+fn overlaps(a: &Edit, b: &Edit) -> bool {
+    let a_start = (a.start_line, a.start_column);
+    let a_end = (a.end_line, a.end_column);
+    let b_start = (b.start_line, b.start_column);
+    let b_end = (b.end_line, b.end_column);
+
+    a_start < b_end && b_start < a_end
 }
 
-fn apply_edit(lines: &mut Vec<String>, edit: &crate::diagnostic::Edit) {
+fn apply_edit(lines: &mut Vec<String>, edit: &Edit) {
     if edit.start_line == 0 || edit.start_line > lines.len() {
         return;
     }
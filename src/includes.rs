@@ -0,0 +1,213 @@
+use crate::parser::{self, AssignmentOperator, Include, Makefile, MissingInclude};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Resolves a path named by an `include` directive in the Makefile at `root` or in a file
+/// it includes. Make resolves them all relative to the directory it runs in, which is taken
+/// to be the directory of `root`.
+pub fn resolve(root: &Path, path: &str) -> PathBuf {
+    match root.parent() {
+        Some(dir) => dir.join(path),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Files named by the `include` directives of `makefile`, read while checking the Makefile
+/// at `root`, with paths that can be resolved without expanding variables or globs, together
+/// with the directive naming them.
+pub fn literal_includes<'a>(
+    root: &Path,
+    makefile: &'a Makefile,
+) -> impl Iterator<Item = (&'a Include, PathBuf)> + 'a {
+    let root = root.to_path_buf();
+    makefile.includes.iter().flat_map(move |include| {
+        let root = root.clone();
+        include
+            .paths
+            .iter()
+            .filter(|path| !path.contains(['$', '*', '?', '[']))
+            .map(move |path| (include, resolve(&root, path)))
+    })
+}
+
+/// Follows the includes of `makefile`, read from `path`, looking for a chain of includes
+/// that leads back to `path`. Returns the directive starting the chain and the files it
+/// passes through, from `path` back to `path`. Files that can't be read or parsed are
+/// skipped.
+pub fn find_cycle<'a>(path: &Path, makefile: &'a Makefile) -> Option<(&'a Include, Vec<PathBuf>)> {
+    let root = path.canonicalize().ok()?;
+    let mut visited = HashSet::new();
+
+    for (include, included) in literal_includes(path, makefile) {
+        let Ok(included) = included.canonicalize() else {
+            continue;
+        };
+        let mut chain = vec![root.clone()];
+        if leads_to(&included, &root, &mut chain, &mut visited) {
+            chain.push(root);
+            return Some((include, chain));
+        }
+    }
+
+    None
+}
+
+/// Whether `file` is `target` or includes it, directly or through other files. `chain`
+/// holds the files on the way to `file`, and on success the whole path to `target`.
+fn leads_to(
+    file: &Path,
+    target: &Path,
+    chain: &mut Vec<PathBuf>,
+    visited: &mut HashSet<PathBuf>,
+) -> bool {
+    if file == target {
+        return true;
+    }
+    // Files seen before either don't lead to `target` or are on a cycle that doesn't pass
+    // through it, which is reported for the files on that cycle
+    if !visited.insert(file.to_path_buf()) {
+        return false;
+    }
+    let Some(makefile) = std::fs::read_to_string(file)
+        .ok()
+        .and_then(|content| parser::parse(&content).ok())
+    else {
+        return false;
+    };
+
+    chain.push(file.to_path_buf());
+    for (_, included) in literal_includes(target, &makefile) {
+        let Ok(included) = included.canonicalize() else {
+            continue;
+        };
+        if leads_to(&included, target, chain, visited) {
+            return true;
+        }
+    }
+    chain.pop();
+    false
+}
+
+/// Reads the files that `makefile`, read from `path`, includes into `makefile.included`,
+/// following their includes in turn, and records the files that required includes at any
+/// depth name but don't exist in `makefile.missing_includes`. Paths are resolved relative to
+/// the directory of `path`, as Make resolves them relative to the directory it runs in.
+///
+/// Variable references in include paths are expanded when the variables are assigned plain
+/// values. Paths with other references or with wildcards are skipped, as are files that
+/// can't be parsed and files already read.
+pub fn follow(path: &Path, makefile: &mut Makefile) {
+    let mut variables = plain_values(makefile);
+    let mut seen: HashSet<PathBuf> = path.canonicalize().into_iter().collect();
+    let mut included = Vec::new();
+    let mut missing = Vec::new();
+
+    for include in &makefile.includes {
+        let mut reader = Reader {
+            root: path,
+            variables: &mut variables,
+            seen: &mut seen,
+            included: &mut included,
+            missing: &mut missing,
+            directive: include,
+        };
+        reader.read(include, None);
+    }
+    makefile.included = included;
+    makefile.missing_includes = missing;
+}
+
+/// State for following the files named by one `include` directive of the root Makefile,
+/// directly or through the files it includes.
+struct Reader<'a> {
+    root: &'a Path,
+    variables: &'a mut HashMap<String, String>,
+    seen: &'a mut HashSet<PathBuf>,
+    included: &'a mut Vec<Makefile>,
+    missing: &'a mut Vec<MissingInclude>,
+    /// The directive of the root Makefile that misses are reported at
+    directive: &'a Include,
+}
+
+impl Reader<'_> {
+    /// Reads the files named by `include`, found in the included file `from`, or in the root
+    /// Makefile when `from` is `None`.
+    fn read(&mut self, include: &Include, from: Option<&str>) {
+        for written in &include.paths {
+            let Some(name) = expand(written, self.variables) else {
+                continue;
+            };
+            if name.contains(['*', '?', '[']) {
+                continue;
+            }
+
+            let file = resolve(self.root, &name);
+            let Ok(canonical) = file.canonicalize() else {
+                if !include.optional {
+                    self.missing.push(MissingInclude {
+                        path: name,
+                        included_from: from.map(String::from),
+                        line: self.directive.line,
+                        column: self.directive.column,
+                    });
+                }
+                continue;
+            };
+            if !self.seen.insert(canonical) {
+                continue;
+            }
+            let Some(mut parsed) = std::fs::read_to_string(&file)
+                .ok()
+                .and_then(|content| parser::parse(&content).ok())
+            else {
+                continue;
+            };
+
+            parsed.path = Some(file);
+            self.variables.extend(plain_values(&parsed));
+            for nested in &parsed.includes {
+                self.read(nested, Some(&name));
+            }
+            self.included.push(parsed);
+        }
+    }
+}
+
+/// Values of the variables of `makefile` that are assigned text without references.
+fn plain_values(makefile: &Makefile) -> HashMap<String, String> {
+    makefile
+        .variables
+        .values()
+        .filter(|variable| {
+            !variable.value.contains('$')
+                && !matches!(
+                    variable.operator,
+                    AssignmentOperator::Append | AssignmentOperator::Shell
+                )
+        })
+        .map(|variable| (variable.name.clone(), variable.value.clone()))
+        .collect()
+}
+
+/// Expands the `$(NAME)` and `${NAME}` references in `path`, or returns `None` if it has
+/// other references or references to variables not in `variables`.
+fn expand(path: &str, variables: &HashMap<String, String>) -> Option<String> {
+    let mut expanded = String::new();
+    let mut rest = path;
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let reference = &rest[start + 1..];
+        let close = match reference.chars().next()? {
+            '(' => ')',
+            '{' => '}',
+            _ => return None,
+        };
+        let end = reference.find(close)?;
+        expanded.push_str(variables.get(&reference[1..end])?);
+        rest = &reference[end + 1..];
+    }
+
+    expanded.push_str(rest);
+    Some(expanded)
+}
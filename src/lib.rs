@@ -1,5 +1,11 @@
+pub mod baseline;
+pub mod cache;
 pub mod config;
 pub mod diagnostic;
+pub mod editorconfig;
 pub mod fix;
+pub mod includes;
+pub mod lint;
+pub mod lsp;
 pub mod parser;
 pub mod rules;
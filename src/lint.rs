@@ -0,0 +1,77 @@
+use crate::config::Config;
+use crate::diagnostic::{Diagnostic, LineIndex};
+use crate::fix;
+use crate::includes;
+use crate::parser::{self, Makefile, ParseError};
+use crate::rules::Rule;
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Lints `content` with the top-level rules of `config`. Returns the diagnostics sorted by
+/// position, without duplicates or findings disabled by `# rumk:` comments. Content that
+/// fails to parse yields a single `MK000` diagnostic.
+pub fn lint(content: &str, config: &Config) -> Vec<Diagnostic> {
+    lint_parsed(
+        parser::parse(content),
+        content,
+        config.rules.iter().map(AsRef::as_ref),
+        |_, _| {},
+    )
+}
+
+/// Lints `content` and applies the safe fixes of its diagnostics. Returns the fixed content,
+/// with its line endings and trailing newline kept, and the diagnostics of the fixed content.
+pub fn lint_and_fix(content: &str, config: &Config) -> (String, Vec<Diagnostic>) {
+    let diagnostics = lint(content, config);
+    let fixed = fix::apply_fixes(content, &diagnostics, false).content;
+    let remaining = lint(&fixed, config);
+    (fixed, remaining)
+}
+
+/// Lints `content` read from `path`, using the rules of the last `[[overrides]]` entry
+/// matching it. With `follow_includes` set in `config`, the files it includes are read too.
+pub fn lint_path(path: &Path, content: &str, config: &Config) -> Vec<Diagnostic> {
+    let parsed = parser::parse(content).map(|mut makefile| {
+        makefile.path = Some(path.to_path_buf());
+        if config.follow_includes {
+            includes::follow(path, &mut makefile);
+        }
+        makefile
+    });
+    lint_parsed(parsed, content, config.rules_for(path).iter(), |_, _| {})
+}
+
+/// Runs `rules` over the result of parsing `content`, calling `on_rule` with the time
+/// each rule took.
+pub fn lint_parsed<'a>(
+    parsed: Result<Makefile, ParseError>,
+    content: &str,
+    rules: impl IntoIterator<Item = &'a dyn Rule>,
+    mut on_rule: impl FnMut(&dyn Rule, Duration),
+) -> Vec<Diagnostic> {
+    let mut diagnostics = match parsed {
+        Ok(makefile) => {
+            let mut diagnostics = Vec::new();
+            for rule in rules {
+                let start = Instant::now();
+                diagnostics.extend(rule.check(&makefile, content));
+                on_rule(rule, start.elapsed());
+            }
+            diagnostics.retain(|d| !makefile.is_suppressed(&d.rule_id, d.line));
+            diagnostics
+        }
+        Err(error) => vec![error.to_diagnostic()],
+    };
+
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    dedup(&mut diagnostics);
+    LineIndex::new(content).populate(&mut diagnostics);
+    diagnostics
+}
+
+/// Removes diagnostics with the same rule id, position and message as an earlier one.
+pub fn dedup(diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen = HashSet::new();
+    diagnostics.retain(|d| seen.insert((d.rule_id.clone(), d.line, d.column, d.message.clone())));
+}
@@ -0,0 +1,213 @@
+use crate::config::Config;
+use crate::diagnostic::{self, Severity};
+use crate::lint;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tower_lsp::jsonrpc::Result as RpcResult;
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+struct Document {
+    content: String,
+    diagnostics: Vec<diagnostic::Diagnostic>,
+}
+
+struct Backend {
+    client: Client,
+    config: Config,
+    documents: Mutex<HashMap<Url, Document>>,
+}
+
+/// Runs the language server over stdin/stdout until the client disconnects.
+pub async fn serve(config: Config) {
+    let (service, socket) = LspService::new(|client| Backend {
+        client,
+        config,
+        documents: Mutex::new(HashMap::new()),
+    });
+
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+}
+
+impl Backend {
+    async fn update(&self, uri: Url, content: String, version: i32) {
+        let diagnostics = self.lint(&uri, &content);
+        let published = diagnostics
+            .iter()
+            .map(|d| to_lsp_diagnostic(d, &content))
+            .collect();
+
+        self.documents.lock().unwrap().insert(
+            uri.clone(),
+            Document {
+                content,
+                diagnostics,
+            },
+        );
+
+        self.client
+            .publish_diagnostics(uri, published, Some(version))
+            .await;
+    }
+
+    fn lint(&self, uri: &Url, content: &str) -> Vec<diagnostic::Diagnostic> {
+        match uri.to_file_path() {
+            Ok(path) => lint::lint_path(&path, content, &self.config),
+            Err(()) => lint::lint(content, &self.config),
+        }
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> RpcResult<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "rumk".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> RpcResult<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let document = params.text_document;
+        self.update(document.uri, document.text, document.version)
+            .await;
+    }
+
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        // Full sync: the last change holds the complete document
+        if let Some(change) = params.content_changes.pop() {
+            let document = params.text_document;
+            self.update(document.uri, change.text, document.version)
+                .await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.lock().unwrap().remove(&uri);
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> RpcResult<Option<CodeActionResponse>> {
+        let documents = self.documents.lock().unwrap();
+        let Some(document) = documents.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let mut actions = Vec::new();
+        for diag in &document.diagnostics {
+            let Some(fix) = &diag.fix else {
+                continue;
+            };
+
+            let lsp_diagnostic = to_lsp_diagnostic(diag, &document.content);
+            let requested = params.range;
+            if lsp_diagnostic.range.end < requested.start
+                || requested.end < lsp_diagnostic.range.start
+            {
+                continue;
+            }
+
+            let edits = fix
+                .edits
+                .iter()
+                .map(|edit| TextEdit {
+                    range: Range::new(
+                        to_position(&document.content, edit.start_line, edit.start_column),
+                        to_position(&document.content, edit.end_line, edit.end_column),
+                    ),
+                    new_text: edit.replacement.clone(),
+                })
+                .collect();
+
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.description.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![lsp_diagnostic]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(params.text_document.uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+
+        Ok(Some(actions))
+    }
+}
+
+fn to_lsp_diagnostic(diag: &diagnostic::Diagnostic, content: &str) -> Diagnostic {
+    // Offsets are always in bytes, whereas some rules report display columns
+    let (start, end) = match (diag.offset, diag.end_offset) {
+        (Some(start), Some(end)) => (
+            offset_position(content, start),
+            offset_position(content, end),
+        ),
+        _ => {
+            let start = to_position(content, diag.line, diag.column);
+            let end = match (diag.end_line, diag.end_column) {
+                (Some(line), Some(column)) => to_position(content, line, column),
+                _ => start,
+            };
+            (start, end)
+        }
+    };
+
+    Diagnostic {
+        range: Range::new(start, end),
+        severity: Some(match diag.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Info => DiagnosticSeverity::INFORMATION,
+        }),
+        code: Some(NumberOrString::String(diag.rule_id.clone())),
+        source: Some("rumk".to_string()),
+        message: diag.message.clone(),
+        ..Default::default()
+    }
+}
+
+/// Converts a 1-based line and byte column into a 0-based LSP position, whose
+/// character offset is counted in UTF-16 code units.
+fn to_position(content: &str, line: usize, column: usize) -> Position {
+    let line_idx = line.saturating_sub(1);
+    let text = content.lines().nth(line_idx).unwrap_or_default();
+    let mut byte_col = column.saturating_sub(1).min(text.len());
+    while !text.is_char_boundary(byte_col) {
+        byte_col -= 1;
+    }
+
+    let character = text[..byte_col].encode_utf16().count();
+    Position::new(line_idx as u32, character as u32)
+}
+
+/// Converts a byte offset into a 0-based LSP position.
+fn offset_position(content: &str, offset: usize) -> Position {
+    let mut offset = offset.min(content.len());
+    while !content.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let line = before.matches('\n').count();
+    Position::new(
+        line as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
@@ -1,16 +1,30 @@
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser, Subcommand};
+use ignore::WalkBuilder;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::time::{Duration, Instant};
 
-mod config;
-mod diagnostic;
-mod fix;
-mod parser;
-mod rules;
+use rumk::baseline::Baseline;
+use rumk::cache::Cache;
+use rumk::config::{Config, OutputStyle};
+use rumk::diagnostic::{Diagnostic, Severity};
+use rumk::{fix, includes, lint, parser, rules};
 
-use crate::config::Config;
-use crate::diagnostic::{Diagnostic, Severity};
+/// No findings at or above the fail level.
+const EXIT_SUCCESS: i32 = 0;
+/// Findings at or above the fail level.
+const EXIT_FINDINGS: i32 = 1;
+/// Invalid command-line arguments.
+const EXIT_USAGE: i32 = 2;
+/// Unreadable paths, invalid config files and other failures that stopped the run.
+const EXIT_ERROR: i32 = 3;
+
+/// Path argument that stands for stdin.
+const STDIN_PATH: &str = "-";
 
 #[derive(Parser)]
 #[command(name = "rumk")]
@@ -20,11 +34,38 @@ struct Cli {
     command: Commands,
 }
 
+impl Cli {
+    /// Rejects the combinations clap can't express: `-` as a path, like `--stdin`, can't be
+    /// combined with `--fix` or `--watch`.
+    fn validate(self) -> Result<Self, clap::Error> {
+        if let Commands::Check {
+            paths, fix, watch, ..
+        } = &self.command
+        {
+            let conflicting = [("--fix", *fix), ("--watch", *watch)]
+                .into_iter()
+                .find_map(|(flag, set)| set.then_some(flag));
+            if let Some(flag) = conflicting.filter(|_| paths.iter().any(|p| p == STDIN_PATH)) {
+                let mut command = Cli::command();
+                command.build();
+                let check = command
+                    .find_subcommand_mut("check")
+                    .expect("check subcommand");
+                return Err(check.error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    format!("the path '{STDIN_PATH}' (stdin) cannot be used with '{flag}'"),
+                ));
+            }
+        }
+        Ok(self)
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     Check {
         #[arg(default_value = "Makefile")]
-        path: PathBuf,
+        paths: Vec<PathBuf>,
 
         #[arg(short, long)]
         config: Option<PathBuf>,
@@ -32,54 +73,815 @@ enum Commands {
         #[arg(long, default_value = "text")]
         format: OutputFormat,
 
+        #[arg(
+            long,
+            default_value_t = 1,
+            value_parser = clap::value_parser!(u8).range(1..=2),
+            help = "JSON schema version (2 emits one document with file paths)"
+        )]
+        json_version: u8,
+
         #[arg(long, help = "Fix any fixable issues")]
         fix: bool,
+
+        #[arg(
+            long,
+            value_name = "IDS",
+            value_delimiter = ',',
+            requires = "fix",
+            help = "Only apply fixes for these rule ids (comma-separated)"
+        )]
+        fix_only: Option<Vec<String>>,
+
+        #[arg(
+            long,
+            requires = "fix",
+            help = "Also apply fixes that may change behavior"
+        )]
+        unsafe_fixes: bool,
+
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 3,
+            value_parser = clap::value_parser!(u32).range(1..),
+            requires = "fix",
+            help = "Re-check and fix again up to N times, for fixes that reveal new issues"
+        )]
+        fix_passes: u32,
+
+        #[arg(short, long, help = "Write diagnostics to a file instead of stdout")]
+        output: Option<PathBuf>,
+
+        #[arg(long, help = "Exit with status 0 even if issues are found")]
+        exit_zero: bool,
+
+        #[arg(
+            long,
+            help = "Exit with a failing status on warnings as well as errors"
+        )]
+        error_on_warning: bool,
+
+        #[arg(
+            long,
+            value_name = "LEVEL",
+            conflicts_with = "error_on_warning",
+            help = "Minimum severity that causes a failing exit status: error, warning or info"
+        )]
+        fail_level: Option<Severity>,
+
+        #[arg(long, help = "Re-check whenever the checked Makefiles change")]
+        watch: bool,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Hide findings recorded in this baseline file"
+        )]
+        baseline: Option<PathBuf>,
+
+        #[arg(
+            long,
+            requires = "baseline",
+            conflicts_with = "watch",
+            help = "Record all current findings in the baseline file"
+        )]
+        write_baseline: bool,
+
+        #[arg(long, help = "Show the number of findings per rule")]
+        statistics: bool,
+
+        #[arg(
+            long,
+            help = "Don't skip files excluded by .gitignore and .ignore files"
+        )]
+        no_ignore: bool,
+
+        #[arg(long, help = "Ignore and don't update the results cache")]
+        no_cache: bool,
+
+        #[arg(
+            long,
+            help = "Only check files that differ from HEAD when inside a git repository"
+        )]
+        changed_only: bool,
+
+        #[arg(
+            long,
+            help = "Report time spent parsing each file and running each rule"
+        )]
+        timings: bool,
+
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Stop outputting diagnostics after the first N"
+        )]
+        max_issues: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Let MK216 check whether undefined prerequisites exist as files"
+        )]
+        check_fs: bool,
+
+        #[arg(
+            long,
+            help = "Read included files, so rules see their definitions and MK227 finds include cycles"
+        )]
+        follow_includes: bool,
+
+        #[arg(long, help = "Validate the config file and exit without linting")]
+        config_check: bool,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["paths", "fix", "watch"],
+            help = "Check Makefile content read from stdin (same as passing '-' as the path)"
+        )]
+        stdin: bool,
+
+        #[arg(
+            long,
+            value_name = "BYTES",
+            default_value_t = 16 * 1024 * 1024,
+            help = "Fail instead of reading more than this many bytes from stdin"
+        )]
+        max_input_bytes: u64,
     },
     Explain {
-        rule: String,
+        #[arg(required_unless_present = "all")]
+        rule: Option<String>,
+
+        #[arg(long, conflicts_with = "rule", help = "Explain every rule")]
+        all: bool,
+
+        #[arg(long, requires = "all", help = "Format the explanations as Markdown")]
+        markdown: bool,
     },
+    /// List every rule
+    Rules {
+        #[arg(long, help = "Print rule metadata, including options, as JSON")]
+        json: bool,
+    },
+    /// Run a language server over stdio
+    Lsp {
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+}
+
+/// Settings shared by every file checked in a run.
+struct CheckOptions {
+    auto_fix: bool,
+    fix_only: Option<Vec<String>>,
+    unsafe_fixes: bool,
+    fix_passes: u32,
+    max_input_bytes: u64,
+    policy: ExitPolicy,
+    baseline: Option<Baseline>,
+    write_baseline: bool,
+    /// Whether directory walks honor `.gitignore` and `.ignore` files
+    respect_ignore: bool,
+    cache: Option<Cache>,
+    timings: Option<Timings>,
+    changed_only: bool,
+    /// Canonical paths of the files changed since `HEAD`, when limiting the run to them
+    changed: Option<HashSet<PathBuf>>,
+}
+
+impl CheckOptions {
+    /// Returns the diagnostics whose fixes should be applied.
+    fn fixes_to_apply(&self, diagnostics: &[Diagnostic]) -> Vec<Diagnostic> {
+        diagnostics
+            .iter()
+            .filter(|d| {
+                self.fix_only
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&d.rule_id))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn is_selected(&self, path: &Path) -> bool {
+        match &self.changed {
+            Some(changed) => std::fs::canonicalize(path).is_ok_and(|path| changed.contains(&path)),
+            None => true,
+        }
+    }
+
+    /// Drops diagnostics covered by the baseline, first recording them when writing one.
+    fn apply_baseline(&mut self, path: &Path, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let Some(baseline) = &mut self.baseline else {
+            return diagnostics;
+        };
+
+        if self.write_baseline {
+            baseline.record(path, &diagnostics);
+        }
+        baseline.filter(path, diagnostics)
+    }
+}
+
+/// Time spent on each part of a run, collected with `--timings`.
+#[derive(Default)]
+struct Timings {
+    parse: Vec<(PathBuf, Duration)>,
+    rules: HashMap<&'static str, Duration>,
+}
+
+impl Timings {
+    /// Writes the rule and parse durations, slowest first, and resets them.
+    fn report(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let mut rules: Vec<_> = self.rules.drain().collect();
+        rules.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let mut parse = std::mem::take(&mut self.parse);
+        parse.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        writeln!(out, "\nRule timings:")?;
+        for (rule_id, duration) in rules {
+            writeln!(out, "{:>12.3?}  {rule_id}", duration)?;
+        }
+        writeln!(out, "\nParse timings:")?;
+        for (path, duration) in parse {
+            writeln!(out, "{:>12.3?}  {}", duration, path.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decides whether the diagnostics of a run should produce a failing exit status.
+#[derive(Debug, Clone, Copy)]
+struct ExitPolicy {
+    exit_zero: bool,
+    fail_level: Severity,
+}
+
+impl ExitPolicy {
+    fn should_fail(&self, diagnostics: &[Diagnostic]) -> bool {
+        if self.exit_zero {
+            return false;
+        }
+
+        diagnostics
+            .iter()
+            .any(|d| d.severity.is_at_least(self.fail_level))
+    }
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
 enum OutputFormat {
     Text,
     Json,
+    Jsonl,
     Github,
+    /// Azure Pipelines logging commands
+    Azure,
+    /// Only the totals of the run
+    Summary,
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// A diagnostic together with the file it was reported for.
+#[derive(Serialize)]
+struct FileDiagnostic<'a> {
+    path: &'a Path,
+    #[serde(flatten)]
+    diagnostic: &'a Diagnostic,
+}
+
+/// Version 2 of the JSON output: a single document for the whole run.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    version: u8,
+    diagnostics: Vec<FileDiagnostic<'a>>,
+}
+
+/// Writes the diagnostics of each checked file in the selected output format.
+struct Reporter {
+    format: OutputFormat,
+    json_version: u8,
+    out: Box<dyn Write>,
+    /// Whether `out` is a report file from `--output`, which is written without colors
+    to_file: bool,
+    style: OutputStyle,
+    buffered: Vec<(PathBuf, Diagnostic)>,
+    /// Findings per rule id, when `--statistics` is enabled
+    statistics: Option<BTreeMap<String, usize>>,
+    summary: Summary,
+    /// Number of diagnostics to output before omitting the rest, from `--max-issues`
+    max_issues: Option<usize>,
+    /// Diagnostics output so far in this run
+    emitted: usize,
+}
+
+/// Totals across every file checked in a run, for the text summary.
+#[derive(Default)]
+struct Summary {
+    files: usize,
+    files_with_issues: usize,
+    /// Files that failed to be read, which are counted in `files` but not checked
+    unreadable: usize,
+    issues: usize,
+    errors: usize,
+    warnings: usize,
+    infos: usize,
+    fixable: usize,
+    /// Fixes applied by `--fix`
+    fixed: usize,
+    /// Fixes skipped by `--fix` because they overlap other fixes
+    conflicting: usize,
+    unsafe_skipped: usize,
+}
+
+impl Summary {
+    /// Writes the totals for `--format summary`.
+    fn write_counts(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "Files checked: {}", self.files)?;
+        writeln!(out, "Files with issues: {}", self.files_with_issues)?;
+        if self.unreadable > 0 {
+            writeln!(out, "Files not read: {}", self.unreadable)?;
+        }
+        writeln!(
+            out,
+            "Issues: {} ({} errors, {} warnings, {} info)",
+            self.issues, self.errors, self.warnings, self.infos
+        )
+    }
+
+    fn print(&self, auto_fix: bool) {
+        use colored::*;
+
+        let plural = |count: usize, word: &str| {
+            if count == 1 {
+                word.to_string()
+            } else {
+                format!("{word}s")
+            }
+        };
+
+        if auto_fix {
+            if self.fixed > 0 {
+                println!("Fixed {} {}", self.fixed, plural(self.fixed, "issue"));
+            }
+            if self.conflicting > 0 {
+                println!(
+                    "Skipped {} conflicting {}, run again to apply them",
+                    self.conflicting,
+                    if self.conflicting == 1 {
+                        "fix"
+                    } else {
+                        "fixes"
+                    }
+                );
+            }
+        }
+
+        if self.unreadable > 0 {
+            println!();
+            println!(
+                "{} {} could not be read",
+                self.unreadable.to_string().red(),
+                plural(self.unreadable, "file")
+            );
+        }
+
+        if self.issues == 0 {
+            // A single clean file is already reported by its "No issues found" line
+            if self.files > 1 && self.unreadable == 0 {
+                println!();
+                println!(
+                    "{} All {} {} checked successfully",
+                    "✓".green(),
+                    self.files,
+                    plural(self.files, "file")
+                );
+            }
+            return;
+        }
+
+        println!();
+        println!(
+            "Found {} {} in {} {} ({} {} checked)",
+            self.issues.to_string().red(),
+            plural(self.issues, "issue"),
+            self.files_with_issues.to_string().red(),
+            plural(self.files_with_issues, "file"),
+            self.files,
+            plural(self.files, "file")
+        );
+
+        if !auto_fix {
+            if self.fixable > 0 {
+                println!("Run with {} to automatically fix issues", "--fix".green());
+            }
+        } else if self.unsafe_skipped > 0 {
+            println!(
+                "{} unsafe fixes available, run with {} to apply them",
+                self.unsafe_skipped,
+                "--unsafe-fixes".green()
+            );
+        }
+    }
+}
+
+impl Reporter {
+    fn report(&mut self, path: &Path, diagnostics: &[Diagnostic]) -> io::Result<()> {
+        self.summary.files += 1;
+        if !diagnostics.is_empty() {
+            self.summary.files_with_issues += 1;
+            self.summary.issues += diagnostics.len();
+            self.summary.fixable += diagnostics.iter().filter(|d| d.fixable).count();
+            for diagnostic in diagnostics {
+                match diagnostic.severity {
+                    Severity::Error => self.summary.errors += 1,
+                    Severity::Warning => self.summary.warnings += 1,
+                    Severity::Info => self.summary.infos += 1,
+                }
+            }
+        }
+
+        if let Some(statistics) = &mut self.statistics {
+            for diagnostic in diagnostics {
+                *statistics.entry(diagnostic.rule_id.clone()).or_default() += 1;
+            }
+        }
+
+        let shown = self
+            .max_issues
+            .map_or(diagnostics.len(), |max| max.saturating_sub(self.emitted))
+            .min(diagnostics.len());
+        self.emitted += shown;
+        let omitted_all = shown == 0 && !diagnostics.is_empty();
+        let diagnostics = &diagnostics[..shown];
+
+        if matches!(self.format, OutputFormat::Json) && self.json_version >= 2 {
+            self.buffered.extend(
+                diagnostics
+                    .iter()
+                    .map(|diagnostic| (path.to_path_buf(), diagnostic.clone())),
+            );
+            return Ok(());
+        }
+        // Don't report "No issues found" for a file whose issues were all cut off
+        if omitted_all && matches!(self.format, OutputFormat::Text) {
+            return Ok(());
+        }
+
+        // Keep ANSI escapes out of report files, but leave the terminal's colors alone
+        let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+        if self.to_file {
+            colored::control::set_override(false);
+        }
+        let result = output_diagnostics(diagnostics, self.format, path, &self.style, &mut self.out);
+        if self.to_file {
+            colored::control::set_override(colorize);
+        }
+        result
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if matches!(self.format, OutputFormat::Json) && self.json_version >= 2 {
+            let report = JsonReport {
+                version: self.json_version,
+                diagnostics: self
+                    .buffered
+                    .iter()
+                    .map(|(path, diagnostic)| FileDiagnostic { path, diagnostic })
+                    .collect(),
+            };
+            let json = serde_json::to_string_pretty(&report).unwrap();
+            writeln!(self.out, "{json}")?;
+            self.buffered.clear();
+        }
+
+        let omitted = self.summary.issues - self.emitted;
+        self.emitted = 0;
+        if omitted > 0 && matches!(self.format, OutputFormat::Text) {
+            writeln!(self.out, "... and {omitted} more")?;
+        }
+
+        if matches!(self.format, OutputFormat::Summary) {
+            self.summary.write_counts(&mut self.out)?;
+        }
+
+        if let Some(statistics) = &mut self.statistics {
+            let statistics = std::mem::take(statistics);
+            // Keep machine-readable output parseable by moving the table to stderr
+            if matches!(self.format, OutputFormat::Text | OutputFormat::Summary) {
+                output_statistics(&statistics, &mut self.out)?;
+            } else {
+                output_statistics(&statistics, &mut io::stderr())?;
+            }
+        }
+
+        self.out.flush()
+    }
+}
+
+fn main() {
+    let code = match run() {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            EXIT_ERROR
+        }
+    };
+    std::process::exit(code);
+}
+
+fn run() -> Result<i32> {
+    let cli = match Cli::try_parse().and_then(Cli::validate) {
+        Ok(cli) => cli,
+        Err(error) => {
+            error.print()?;
+            // `--help` and `--version` are reported as errors too, but on stdout
+            return Ok(if error.use_stderr() {
+                EXIT_USAGE
+            } else {
+                EXIT_SUCCESS
+            });
+        }
+    };
 
     match cli.command {
         Commands::Check {
-            path,
+            paths,
             config,
             format,
+            json_version,
             fix,
+            fix_only,
+            unsafe_fixes,
+            fix_passes,
+            output,
+            exit_zero,
+            error_on_warning,
+            fail_level,
+            watch,
+            baseline,
+            write_baseline,
+            statistics,
+            no_ignore,
+            no_cache,
+            timings,
+            changed_only,
+            check_fs,
+            follow_includes,
+            max_issues,
+            config_check,
+            stdin,
+            max_input_bytes,
         } => {
-            let config = load_config(config)?;
-            check_path(&path, &config, format, fix)?;
+            if config_check {
+                return check_config(config);
+            }
+            let paths = if stdin {
+                vec![PathBuf::from(STDIN_PATH)]
+            } else {
+                expand_globs(paths)?
+            };
+            let mut config = load_config(config)?;
+            if check_fs {
+                let options = HashMap::from([("check_fs".to_string(), toml::Value::Boolean(true))]);
+                config.configure_rule("MK216", &options)?;
+            }
+            if follow_includes {
+                config.follow_includes = true;
+                let options =
+                    HashMap::from([("follow_includes".to_string(), toml::Value::Boolean(true))]);
+                config.configure_rule("MK227", &options)?;
+            }
+            let to_file = output.is_some();
+            let out: Box<dyn Write> = match output {
+                Some(output) => Box::new(File::create(&output).with_context(|| {
+                    format!("Failed to create output file: {}", output.display())
+                })?),
+                None => Box::new(io::stdout()),
+            };
+            let mut options = CheckOptions {
+                auto_fix: fix,
+                fix_only,
+                unsafe_fixes,
+                fix_passes,
+                max_input_bytes,
+                policy: ExitPolicy {
+                    exit_zero,
+                    fail_level: if error_on_warning {
+                        Severity::Warning
+                    } else {
+                        fail_level.or(config.fail_level).unwrap_or(Severity::Error)
+                    },
+                },
+                baseline: match &baseline {
+                    Some(path) if write_baseline => Some(Baseline::new(path)),
+                    Some(path) => Some(Baseline::load(path)?),
+                    None => None,
+                },
+                write_baseline,
+                respect_ignore: !no_ignore,
+                // Cached results would leave nothing to time, or miss file system changes
+                cache: if no_cache || timings || config.reads_other_files() {
+                    None
+                } else {
+                    Cache::open(&config)
+                },
+                timings: timings.then(Timings::default),
+                changed_only,
+                changed: None,
+            };
+            let mut reporter = Reporter {
+                format,
+                json_version,
+                out,
+                to_file,
+                style: config.output.clone(),
+                buffered: Vec::new(),
+                statistics: statistics.then(BTreeMap::new),
+                summary: Summary::default(),
+                max_issues,
+                emitted: 0,
+            };
+            if watch {
+                watch_paths(&paths, || {
+                    if matches!(reporter.format, OutputFormat::Text) {
+                        // Clear the screen before reprinting results
+                        print!("\x1B[2J\x1B[1;1H");
+                    }
+                    check_paths(&paths, &config, &mut options, &mut reporter)?;
+                    Ok(())
+                })?;
+                return Ok(EXIT_SUCCESS);
+            }
+
+            let code = check_paths(&paths, &config, &mut options, &mut reporter)?;
+            if let (Some(path), Some(recorded)) = (&baseline, &options.baseline) {
+                if write_baseline {
+                    recorded.save(path)?;
+                    eprintln!(
+                        "Wrote {} findings to baseline {}",
+                        recorded.len(),
+                        path.display()
+                    );
+                }
+            }
+            if code != EXIT_SUCCESS {
+                return Ok(code);
+            }
         }
-        Commands::Explain { rule } => {
-            explain_rule(&rule)?;
+        Commands::Explain {
+            rule,
+            all,
+            markdown,
+        } => {
+            if all {
+                println!("{}", rules::get_all_explanations(markdown));
+            } else if let Some(rule) = rule {
+                explain_rule(&rule)?;
+            }
+        }
+        Commands::Rules { json } => list_rules(json)?,
+        Commands::Lsp { config } => {
+            let config = load_config(config)?;
+            tokio::runtime::Runtime::new()?.block_on(rumk::lsp::serve(config));
         }
     }
 
-    Ok(())
+    Ok(EXIT_SUCCESS)
+}
+
+/// Reports problems in the given or discovered config file, returning the exit status.
+fn check_config(path: Option<PathBuf>) -> Result<i32> {
+    let Some(path) = path.or_else(Config::find_path) else {
+        anyhow::bail!("No config file found");
+    };
+
+    let problems = Config::validate_file(&path)?;
+    for problem in &problems {
+        eprintln!("{}: {problem}", path.display());
+    }
+    if problems.is_empty() {
+        println!("{} is valid", path.display());
+        Ok(EXIT_SUCCESS)
+    } else {
+        Ok(EXIT_FINDINGS)
+    }
 }
 
 fn load_config(path: Option<PathBuf>) -> Result<Config> {
-    match path {
-        Some(path) => Config::from_file(&path),
-        None => Ok(Config::find_and_load().unwrap_or_else(|_| Config::default())),
+    use colored::*;
+
+    let config = match path {
+        Some(path) => Config::from_file(&path)?,
+        None => Config::find_and_load()?,
+    };
+    for warning in &config.warnings {
+        eprintln!("{}: {warning}", "Warning".yellow());
+    }
+    Ok(config)
+}
+
+/// Expands glob patterns such as `**/*.mk` in path arguments, relative to the working
+/// directory. Arguments naming an existing path are kept as-is even if they contain glob
+/// characters.
+fn expand_globs(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
+
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        if path.exists() || !pattern.contains(['*', '?', '[']) {
+            expanded.push(path);
+            continue;
+        }
+
+        let matches = glob::glob(&pattern)
+            .with_context(|| format!("Invalid glob pattern: {pattern}"))?
+            .collect::<Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            anyhow::bail!("No files match pattern '{pattern}'");
+        }
+        expanded.extend(matches);
     }
+
+    Ok(expanded)
 }
 
-fn check_path(path: &PathBuf, config: &Config, format: OutputFormat, auto_fix: bool) -> Result<()> {
-    if path.is_file() {
-        check_file(path, config, format, auto_fix)
+/// Checks every path, then completes the report with the summary and timings of the run.
+/// Returns the exit status: `EXIT_ERROR` if a file couldn't be read, otherwise
+/// `EXIT_FINDINGS` if the findings fail the run.
+fn check_paths(
+    paths: &[PathBuf],
+    config: &Config,
+    options: &mut CheckOptions,
+    reporter: &mut Reporter,
+) -> Result<i32> {
+    if options.changed_only {
+        options.changed = changed_files();
+    }
+
+    let mut failed = false;
+    for path in paths {
+        failed |= check_path(path, config, options, reporter)?;
+    }
+
+    reporter.finish()?;
+    let summary = std::mem::take(&mut reporter.summary);
+    if matches!(reporter.format, OutputFormat::Text) {
+        summary.print(options.auto_fix);
+    }
+    if let Some(timings) = &mut options.timings {
+        timings.report(&mut io::stderr())?;
+    }
+
+    Ok(if summary.unreadable > 0 {
+        EXIT_ERROR
+    } else if failed {
+        EXIT_FINDINGS
+    } else {
+        EXIT_SUCCESS
+    })
+}
+
+/// Returns the files that are modified, staged or untracked relative to `HEAD`, or `None`
+/// when the working directory isn't inside a git repository.
+fn changed_files() -> Option<HashSet<PathBuf>> {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = std::process::Command::new("git").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let root = PathBuf::from(git(&["rev-parse", "--show-toplevel"])?.trim_end());
+    let modified = git(&["diff", "--name-only", "HEAD"])?;
+    let untracked = git(&["ls-files", "--others", "--exclude-standard", "--full-name"])?;
+
+    Some(
+        modified
+            .lines()
+            .chain(untracked.lines())
+            .filter_map(|name| std::fs::canonicalize(root.join(name)).ok())
+            .collect(),
+    )
+}
+
+fn check_path(
+    path: &PathBuf,
+    config: &Config,
+    options: &mut CheckOptions,
+    reporter: &mut Reporter,
+) -> Result<bool> {
+    if path.as_os_str() == STDIN_PATH {
+        check_stdin(config, options, reporter)
+    } else if path.is_file() {
+        if !options.is_selected(path) {
+            return Ok(false);
+        }
+        check_file(path, config, options, reporter)
     } else if path.is_dir() {
-        check_directory(path, config, format, auto_fix)
+        check_directory(path, config, options, reporter)
     } else {
         anyhow::bail!(
             "Path '{}' is neither a file nor a directory",
@@ -88,116 +890,160 @@ fn check_path(path: &PathBuf, config: &Config, format: OutputFormat, auto_fix: b
     }
 }
 
+/// Checks Makefile content read from stdin, reporting it under the path `-`.
+fn check_stdin(
+    config: &Config,
+    options: &mut CheckOptions,
+    reporter: &mut Reporter,
+) -> Result<bool> {
+    if options.auto_fix {
+        anyhow::bail!("Cannot fix content read from stdin");
+    }
+
+    let content = read_limited(io::stdin().lock(), options.max_input_bytes)?;
+    let path = Path::new(STDIN_PATH);
+    let diagnostics = lint_file(path, &content, config, None, options.timings.as_mut());
+    let diagnostics = options.apply_baseline(path, diagnostics);
+    reporter.report(path, &diagnostics)?;
+
+    Ok(options.policy.should_fail(&diagnostics))
+}
+
+/// Reads `reader` to the end as UTF-8, failing once more than `limit` bytes arrive so a
+/// runaway pipe isn't buffered in full.
+fn read_limited(reader: impl io::Read, limit: u64) -> Result<String> {
+    use std::io::Read;
+
+    let mut content = String::new();
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_string(&mut content)
+        .context("Failed to read stdin")?;
+    if content.len() as u64 > limit {
+        anyhow::bail!(
+            "Input from stdin exceeds the limit of {limit} bytes (see --max-input-bytes)"
+        );
+    }
+    Ok(content)
+}
+
 fn check_directory(
     dir: &PathBuf,
     config: &Config,
-    format: OutputFormat,
-    auto_fix: bool,
-) -> Result<()> {
+    options: &mut CheckOptions,
+    reporter: &mut Reporter,
+) -> Result<bool> {
     use colored::*;
 
-    let mut total_files = 0;
-    let mut files_with_issues = 0;
-    let mut total_issues = 0;
     let mut has_errors = false;
 
-    for entry in WalkDir::new(dir)
-        .into_iter()
+    // Sort paths so output doesn't depend on the filesystem's directory order
+    let mut paths: Vec<PathBuf> = WalkBuilder::new(dir)
+        .standard_filters(options.respect_ignore)
+        .hidden(false)
+        .require_git(false)
+        .build()
         .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        let path = entry.path();
-
-        // Check if this looks like a Makefile
-        if is_makefile(path) {
-            total_files += 1;
-
-            match std::fs::read_to_string(path) {
-                Ok(content) => match parser::parse(&content) {
-                    Ok(makefile) => {
-                        let mut diagnostics = Vec::new();
-
-                        for rule in &config.rules {
-                            let rule_diagnostics = rule.check(&makefile, &content);
-                            diagnostics.extend(rule_diagnostics);
-                        }
-
-                        diagnostics.sort_by_key(|d| (d.line, d.column));
-
-                        if auto_fix && !diagnostics.is_empty() {
-                            let fixed_content = fix::apply_fixes(&content, &diagnostics);
-                            if fixed_content != content {
-                                std::fs::write(path, fixed_content)?;
-                            }
-                        }
-
-                        if !diagnostics.is_empty() {
-                            files_with_issues += 1;
-                            total_issues += diagnostics.len();
-                            has_errors = has_errors
-                                || diagnostics
-                                    .iter()
-                                    .any(|d| matches!(d.severity, diagnostic::Severity::Error));
-                        }
-
-                        output_diagnostics(&diagnostics, format, path);
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "{}: Failed to parse: {}",
-                            path.display().to_string().red(),
-                            e
-                        );
-                        files_with_issues += 1;
-                        has_errors = true;
-                    }
-                },
-                Err(e) => {
-                    eprintln!(
-                        "{}: Failed to read: {}",
-                        path.display().to_string().red(),
-                        e
-                    );
-                    files_with_issues += 1;
-                    has_errors = true;
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .map(|e| e.into_path())
+        .filter(|path| {
+            let relative = path.strip_prefix(dir).unwrap_or(path);
+
+            // Check if this looks like a Makefile, as adjusted by the `[files]` patterns
+            !config.files.is_excluded(relative)
+                && (is_makefile(path) || config.files.is_included(relative))
+        })
+        .filter(|path| options.is_selected(path))
+        .collect();
+    paths.sort();
+
+    for path in &paths {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let diagnostics = lint_file(
+                    path,
+                    &content,
+                    config,
+                    options.cache.as_ref(),
+                    options.timings.as_mut(),
+                );
+                let mut diagnostics = options.apply_baseline(path, diagnostics);
+
+                if options.auto_fix && !diagnostics.is_empty() {
+                    let result;
+                    (result, diagnostics) = fix_file(path, content, diagnostics, config, options)?;
+                    reporter.summary.fixed += result.applied;
+                    reporter.summary.conflicting += result.skipped;
+                    reporter.summary.unsafe_skipped += result.unsafe_skipped;
                 }
+
+                has_errors = has_errors || options.policy.should_fail(&diagnostics);
+
+                reporter.report(path, &diagnostics)?;
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}: Failed to read: {}",
+                    path.display().to_string().red(),
+                    e
+                );
+                // Counted apart from findings, so `--exit-zero` doesn't hide the failure
+                reporter.summary.files += 1;
+                reporter.summary.unreadable += 1;
             }
         }
     }
 
-    // Print summary for text format
-    if matches!(format, OutputFormat::Text) && total_files > 0 {
-        println!();
-        if total_issues == 0 {
-            println!(
-                "{} All {} {} checked successfully",
-                "✓".green(),
-                total_files,
-                if total_files == 1 { "file" } else { "files" }
-            );
+    Ok(has_errors)
+}
+
+/// Calls `rerun` once the watch is established and again whenever Makefiles under `paths`
+/// change, blocking forever. Bursts of events (e.g. an editor writing a file in several
+/// steps) are coalesced. Errors from `rerun` are printed and don't end the watch.
+fn watch_paths(paths: &[PathBuf], mut rerun: impl FnMut() -> Result<()>) -> Result<()> {
+    use notify::{RecursiveMode, Watcher};
+    use std::sync::mpsc;
+
+    const DEBOUNCE: Duration = Duration::from_millis(200);
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+
+    // Watch a file's directory rather than the file itself, so that editors replacing the
+    // file on save don't end the watch
+    let mut files = Vec::new();
+    for path in paths {
+        let watched = if path.is_file() {
+            files.extend(path.file_name());
+            let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+            parent.unwrap_or(Path::new("."))
         } else {
-            println!(
-                "Found {} {} in {} {} ({} {} checked)",
-                total_issues.to_string().red(),
-                if total_issues == 1 { "issue" } else { "issues" },
-                files_with_issues.to_string().red(),
-                if files_with_issues == 1 {
-                    "file"
-                } else {
-                    "files"
-                },
-                total_files,
-                if total_files == 1 { "file" } else { "files" }
-            );
+            path
+        };
+        watcher.watch(watched, RecursiveMode::Recursive)?;
+    }
 
-            if !auto_fix {
-                println!("Run with {} to automatically fix issues", "--fix".green());
-            }
+    let is_relevant = |event: &notify::Event| {
+        !event.kind.is_access()
+            && event.paths.iter().any(|changed| {
+                is_makefile(changed) || changed.file_name().is_some_and(|f| files.contains(&f))
+            })
+    };
+
+    let mut run = || {
+        if let Err(error) = rerun() {
+            eprintln!("Error: {error:?}");
+        }
+    };
+
+    run();
+    while let Ok(event) = receiver.recv() {
+        if !event.is_ok_and(|event| is_relevant(&event)) {
+            continue;
         }
-    }
 
-    if has_errors {
-        std::process::exit(1);
+        while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+        run();
     }
 
     Ok(())
@@ -214,88 +1060,167 @@ fn is_makefile(path: &std::path::Path) -> bool {
     }
 }
 
-fn check_file(path: &PathBuf, config: &Config, format: OutputFormat, auto_fix: bool) -> Result<()> {
-    let content = std::fs::read_to_string(path)?;
-    let makefile = parser::parse(&content)?;
+/// Runs the configured rules over a file's content, reusing cached results when the file
+/// is unchanged since the last run. A file that fails to parse yields a single `MK000`
+/// diagnostic.
+fn lint_file(
+    path: &Path,
+    content: &str,
+    config: &Config,
+    cache: Option<&Cache>,
+    mut timings: Option<&mut Timings>,
+) -> Vec<Diagnostic> {
+    if let Some(diagnostics) = cache.and_then(|cache| cache.get(path, content)) {
+        return diagnostics;
+    }
+
+    let start = Instant::now();
+    let parsed = parser::parse(content).map(|mut makefile| {
+        makefile.path = Some(path.to_path_buf());
+        if config.follow_includes {
+            includes::follow(path, &mut makefile);
+        }
+        makefile
+    });
+    if let Some(timings) = timings.as_deref_mut() {
+        timings.parse.push((path.to_path_buf(), start.elapsed()));
+    }
 
-    let mut diagnostics = Vec::new();
+    let diagnostics = lint::lint_parsed(
+        parsed,
+        content,
+        config.rules_for(path).iter(),
+        |rule, elapsed| {
+            if let Some(timings) = timings.as_deref_mut() {
+                *timings.rules.entry(rule.id()).or_default() += elapsed;
+            }
+        },
+    );
 
-    for rule in &config.rules {
-        let rule_diagnostics = rule.check(&makefile, &content);
-        diagnostics.extend(rule_diagnostics);
+    if let Some(cache) = cache {
+        cache.put(path, content, &diagnostics);
     }
+    diagnostics
+}
 
-    diagnostics.sort_by_key(|d| (d.line, d.column));
+fn check_file(
+    path: &PathBuf,
+    config: &Config,
+    options: &mut CheckOptions,
+    reporter: &mut Reporter,
+) -> Result<bool> {
+    let content = std::fs::read_to_string(path)?;
+    let diagnostics = lint_file(
+        path,
+        &content,
+        config,
+        options.cache.as_ref(),
+        options.timings.as_mut(),
+    );
+    let mut diagnostics = options.apply_baseline(path, diagnostics);
 
-    if auto_fix {
-        let fixed_content = fix::apply_fixes(&content, &diagnostics);
-        if fixed_content != content {
-            std::fs::write(path, fixed_content)?;
-            println!(
-                "Fixed {} issues",
-                diagnostics.iter().filter(|d| d.fixable).count()
-            );
-        }
+    if options.auto_fix {
+        let result;
+        (result, diagnostics) = fix_file(path, content, diagnostics, config, options)?;
+        reporter.summary.fixed += result.applied;
+        reporter.summary.conflicting += result.skipped;
+        reporter.summary.unsafe_skipped += result.unsafe_skipped;
     }
 
-    output_diagnostics(&diagnostics, format, path);
+    reporter.report(path, &diagnostics)?;
 
-    // Print summary for text format
-    if matches!(format, OutputFormat::Text) && !diagnostics.is_empty() {
-        use colored::*;
+    Ok(options.policy.should_fail(&diagnostics))
+}
 
-        let issue_count = diagnostics.len();
-        let fixable_count = diagnostics.iter().filter(|d| d.fixable).count();
+/// Applies the fixes for `diagnostics` to the file at `path` and re-checks the result,
+/// repeating for up to `fix_passes` passes while fixes still change the content. Returns
+/// the number of fixes applied over all passes, the skipped counts of the last pass, and
+/// the diagnostics that remain.
+fn fix_file(
+    path: &Path,
+    mut content: String,
+    mut diagnostics: Vec<Diagnostic>,
+    config: &Config,
+    options: &CheckOptions,
+) -> Result<(fix::FixResult, Vec<Diagnostic>)> {
+    let original = content.clone();
+    let mut applied = 0;
+    let mut skipped = 0;
+    let mut unsafe_skipped = 0;
 
-        println!();
-        println!(
-            "Found {} {} in 1 file (1 file checked)",
-            issue_count.to_string().red(),
-            if issue_count == 1 { "issue" } else { "issues" }
+    for _ in 0..options.fix_passes {
+        let result = fix::apply_fixes(
+            &content,
+            &options.fixes_to_apply(&diagnostics),
+            options.unsafe_fixes,
         );
+        skipped = result.skipped;
+        unsafe_skipped = result.unsafe_skipped;
+        if result.content == content {
+            break;
+        }
 
-        if fixable_count > 0 && !auto_fix {
-            println!("Run with {} to automatically fix issues", "--fix".green());
+        applied += result.applied;
+        content = result.content;
+        diagnostics = lint_file(path, &content, config, None, None);
+        if let Some(baseline) = &options.baseline {
+            diagnostics = baseline.filter(path, diagnostics);
         }
     }
 
-    let has_errors = diagnostics
-        .iter()
-        .any(|d| matches!(d.severity, Severity::Error));
-    if has_errors {
-        std::process::exit(1);
+    if content != original {
+        std::fs::write(path, &content)?;
     }
-
-    Ok(())
+    let result = fix::FixResult {
+        content,
+        applied,
+        skipped,
+        unsafe_skipped,
+    };
+    Ok((result, diagnostics))
 }
 
-fn output_diagnostics(diagnostics: &[Diagnostic], format: OutputFormat, path: &Path) {
+fn output_diagnostics(
+    diagnostics: &[Diagnostic],
+    format: OutputFormat,
+    path: &Path,
+    style: &OutputStyle,
+    out: &mut dyn Write,
+) -> io::Result<()> {
     match format {
-        OutputFormat::Text => output_text(diagnostics, path),
-        OutputFormat::Json => output_json(diagnostics),
-        OutputFormat::Github => output_github(diagnostics, path),
+        OutputFormat::Text => output_text(diagnostics, path, style, out),
+        OutputFormat::Json => output_json(diagnostics, out),
+        OutputFormat::Jsonl => output_jsonl(diagnostics, path, out),
+        OutputFormat::Github => output_github(diagnostics, path, out),
+        OutputFormat::Azure => output_azure(diagnostics, path, out),
+        OutputFormat::Summary => Ok(()),
     }
 }
 
-fn output_text(diagnostics: &[Diagnostic], path: &Path) {
+fn output_text(
+    diagnostics: &[Diagnostic],
+    path: &Path,
+    style: &OutputStyle,
+    out: &mut dyn Write,
+) -> io::Result<()> {
     use colored::*;
 
     if diagnostics.is_empty() {
-        println!("{} No issues found in {}", "✓".green(), path.display());
-        return;
+        return writeln!(out, "{} No issues found in {}", "✓".green(), path.display());
     }
 
     for diag in diagnostics {
-        let rule_color = match diag.severity {
-            Severity::Error => "red",
-            Severity::Warning => "yellow",
-            Severity::Info => "cyan",
-        };
+        let rule_color = style.severity_color(diag.severity);
 
         // Format: filename:line:column: [RULE_ID] message [*]
-        let fix_indicator = if diag.fixable { " [*]" } else { "" };
+        let fix_indicator = if diag.fixable && !style.fix_indicator.is_empty() {
+            format!(" {}", style.fix_indicator)
+        } else {
+            String::new()
+        };
 
-        println!(
+        writeln!(
+            out,
             "{}:{}:{}: {} {}{}",
             path.display().to_string().cyan(),
             diag.line,
@@ -303,16 +1228,27 @@ fn output_text(diagnostics: &[Diagnostic], path: &Path) {
             format!("[{}]", diag.rule_id).color(rule_color),
             diag.message,
             fix_indicator.yellow()
-        );
+        )?;
     }
+
+    Ok(())
 }
 
-fn output_json(diagnostics: &[Diagnostic]) {
+fn output_json(diagnostics: &[Diagnostic], out: &mut dyn Write) -> io::Result<()> {
     let json = serde_json::to_string_pretty(diagnostics).unwrap();
-    println!("{json}");
+    writeln!(out, "{json}")
 }
 
-fn output_github(diagnostics: &[Diagnostic], path: &Path) {
+fn output_jsonl(diagnostics: &[Diagnostic], path: &Path, out: &mut dyn Write) -> io::Result<()> {
+    for diagnostic in diagnostics {
+        let json = serde_json::to_string(&FileDiagnostic { path, diagnostic }).unwrap();
+        writeln!(out, "{json}")?;
+    }
+
+    Ok(())
+}
+
+fn output_github(diagnostics: &[Diagnostic], path: &Path, out: &mut dyn Write) -> io::Result<()> {
     for diag in diagnostics {
         let level = match diag.severity {
             Severity::Error => "error",
@@ -320,15 +1256,99 @@ fn output_github(diagnostics: &[Diagnostic], path: &Path) {
             Severity::Info => "notice",
         };
 
-        println!(
+        writeln!(
+            out,
             "::{} file={},line={},col={}::{}",
             level,
             path.display(),
             diag.line,
             diag.column,
             diag.message
-        );
+        )?;
+    }
+
+    Ok(())
+}
+
+fn output_azure(diagnostics: &[Diagnostic], path: &Path, out: &mut dyn Write) -> io::Result<()> {
+    for diag in diagnostics {
+        // Azure has no level below warning
+        let level = match diag.severity {
+            Severity::Error => "error",
+            Severity::Warning | Severity::Info => "warning",
+        };
+
+        writeln!(
+            out,
+            "##vso[task.logissue type={};sourcepath={};linenumber={};columnnumber={}]{}",
+            level,
+            path.display(),
+            diag.line,
+            diag.column,
+            diag.message
+        )?;
+    }
+
+    Ok(())
+}
+
+fn output_statistics(statistics: &BTreeMap<String, usize>, out: &mut dyn Write) -> io::Result<()> {
+    let names: HashMap<&str, &str> = rules::get_all_rules()
+        .iter()
+        .map(|rule| (rule.id(), rule.name()))
+        .collect();
+
+    let mut counts: Vec<(&String, &usize)> = statistics.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+    writeln!(out)?;
+    for (rule_id, count) in counts {
+        let name = names.get(rule_id.as_str()).copied().unwrap_or_default();
+        writeln!(out, "{count:>6}  {rule_id:<6}  {name}")?;
     }
+
+    Ok(())
+}
+
+/// Rule metadata printed by `rumk rules --json`.
+#[derive(Serialize)]
+struct RuleMetadata {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    category: rules::RuleCategory,
+    /// Whether the rule runs without a config file
+    default_enabled: bool,
+    /// Whether the rule only runs with a config file that enables it explicitly
+    opt_in: bool,
+    fixable: bool,
+    options: Vec<rules::OptionSpec>,
+}
+
+fn list_rules(json: bool) -> Result<()> {
+    let defaults: Vec<&str> = rules::get_default_rules().iter().map(|r| r.id()).collect();
+    let metadata: Vec<RuleMetadata> = rules::get_all_rules()
+        .iter()
+        .map(|rule| RuleMetadata {
+            id: rule.id(),
+            name: rule.name(),
+            description: rule.description(),
+            category: rule.category(),
+            default_enabled: defaults.contains(&rule.id()),
+            opt_in: rule.opt_in(),
+            fixable: rule.fixable(),
+            options: rule.options(),
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&metadata)?);
+    } else {
+        for rule in &metadata {
+            println!("{}  {}", rule.id, rule.name);
+        }
+    }
+    Ok(())
 }
 
 fn explain_rule(rule_id: &str) -> Result<()> {
@@ -1,38 +1,275 @@
-use anyhow::{bail, Result};
+use crate::diagnostic::{Diagnostic, Severity};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct Makefile {
     pub rules: Vec<Rule>,
+    /// The last assignment of each variable
     pub variables: HashMap<String, Variable>,
+    /// Every assignment in file order, including those a later one of the same name replaced
+    pub assignments: Vec<Variable>,
     pub phonies: Vec<String>,
+    /// Lines of the `.PHONY:` declarations that `phonies` was collected from
+    pub phony_lines: Vec<usize>,
+    /// Prerequisites of `.NAME:` declarations other than `.PHONY`, such as `.SECONDARY`,
+    /// keyed by the target name; names aren't checked against the targets Make knows
+    pub special_targets: HashMap<String, Vec<String>>,
+    /// Lines of the declarations that `special_targets` was collected from, keyed the same way
+    pub special_target_lines: HashMap<String, Vec<usize>>,
+    /// Lines of rules whose targets start with `.`, such as suffix rules and special
+    /// targets, which aren't in `rules` but may have recipes
+    pub dotted_rule_lines: Vec<usize>,
+    pub includes: Vec<Include>,
+    pub comments: Vec<Comment>,
+    /// Value of an explicit `.DEFAULT_GOAL` assignment
+    pub default_goal: Option<String>,
+    /// Search paths from `vpath` directives and `VPATH` assignments
+    pub vpaths: Vec<Vpath>,
+    /// Variables named by `export` directives, with or without an assignment
+    pub exports: Vec<Export>,
+    /// Every `$(...)`, `${...}` and `$X` reference outside comments, in file order
+    pub references: Vec<VarRef>,
+    /// Line whose trailing `\` continues into nothing but blank lines and the end of file
+    pub dangling_continuation: Option<usize>,
+    /// Line ranges where `# rumk: disable` comments turn rules off
+    pub suppressions: Vec<Suppression>,
+    /// File the Makefile was read from; `parse` leaves it unset for callers to fill in
+    pub path: Option<PathBuf>,
+    /// Files read through `include` directives, directly or indirectly, when includes are
+    /// followed; each has its `path` set, so its elements can be told apart from this file's
+    pub included: Vec<Makefile>,
+    /// Files named by this file's required `include` directives that don't exist, when
+    /// includes are followed
+    pub missing_includes: Vec<MissingInclude>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Rule {
     pub targets: Vec<String>,
+    pub prerequisites: Vec<String>,
+    pub order_only_prerequisites: Vec<String>,
+    pub double_colon: bool,
     pub recipes: Vec<Recipe>,
+    /// Blank lines between the rule line and its last recipe line
+    pub blank_lines: Vec<usize>,
+    /// Text of the `## ` help comments directly above the rule, one line per comment
+    pub doc: Option<String>,
     pub line: usize,
     pub column: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct Recipe {
+    /// The logical command, with backslash-continued lines joined
     pub command: String,
     pub line: usize,
+    /// Last physical line of the command
+    pub end_line: usize,
     pub column: usize,
+    /// The text before the prefix: the indentation, or the rule line up to the command for
+    /// an inline recipe
     pub indentation: String,
+    /// Written after a `;` on the rule line rather than on a line of its own
+    pub inline: bool,
+    /// The `@`, `-` and `+` prefix characters before the command, as written
+    pub prefix: String,
+    /// `@`: Make doesn't echo the command
+    pub silent: bool,
+    /// `-`: Make ignores a failing exit status
+    pub ignore_error: bool,
+    /// `+`: the command also runs under `make -n`
+    pub always_run: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comment {
+    /// Comment text following the `#`
+    pub text: String,
+    pub line: usize,
+    /// Column of the `#`
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// `$(NAME)`, `${NAME}`, `$N` or a substitution reference like `$(OBJS:.o=.c)`. Names
+    /// computed from other references, as in `$($(NAME)_FLAGS)`, contain a `$`.
+    Variable,
+    /// `$@`, `$<`, `$(@D)` and the other automatic variables
+    Automatic,
+    /// A call of a built-in function such as `$(shell ...)`, named by `name`
+    Function,
+}
+
+/// A variable reference or function call, located at its `$`. The variable named by
+/// `ifdef`/`ifndef` and by the first argument of `call`, `value`, `origin` and `flavor`
+/// is recorded as a `Variable` reference too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarRef {
+    pub name: String,
+    pub kind: ReferenceKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Lines on which a rule is turned off by `# rumk: disable ...` or
+/// `# rumk: disable-next-line ...` comments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suppression {
+    /// Rule id, or `None` for every rule
+    pub rule: Option<String>,
+    pub start_line: usize,
+    /// Last suppressed line, or `None` if no `# rumk: enable` ends the range
+    pub end_line: Option<usize>,
+}
+
+impl Suppression {
+    fn covers(&self, rule_id: &str, line: usize) -> bool {
+        self.rule.as_deref().is_none_or(|rule| rule == rule_id)
+            && line >= self.start_line
+            && self.end_line.is_none_or(|end| line <= end)
+    }
+}
+
+impl Makefile {
+    /// Whether a `# rumk:` comment directive turns off `rule_id` on `line`.
+    pub fn is_suppressed(&self, rule_id: &str, line: usize) -> bool {
+        self.suppressions
+            .iter()
+            .any(|suppression| suppression.covers(rule_id, line))
+    }
+
+    /// This file followed by the files it includes, for checks that need the definitions
+    /// of every file Make reads.
+    pub fn files(&self) -> impl Iterator<Item = &Makefile> {
+        std::iter::once(self).chain(&self.included)
+    }
+
+    /// Whether `.PHONY` lists `target` in this file or a file it includes.
+    pub fn is_phony(&self, target: &str) -> bool {
+        self.files()
+            .any(|file| file.phonies.iter().any(|phony| phony == target))
+    }
+
+    /// Every assignment of the variable `name`, in file order.
+    pub fn assignments_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Variable> {
+        self.assignments
+            .iter()
+            .filter(move |variable| variable.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub name: String,
+    pub line: usize,
+    /// Column of the variable name
+    pub column: usize,
+}
+
+/// A file named by an `include` directive that doesn't exist.
+#[derive(Debug, Clone)]
+pub struct MissingInclude {
+    /// The path as named by the directive, with variables expanded
+    pub path: String,
+    /// The included file whose directive names the path, or `None` for the root Makefile
+    pub included_from: Option<String>,
+    /// Position of the root Makefile's directive that the file is included through
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Include {
+    pub paths: Vec<String>,
+    /// `-include` / `sinclude`, which don't fail when the file is missing
+    pub optional: bool,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Vpath {
+    /// Pattern of a `vpath` directive; `None` for the general `VPATH` search path
+    pub pattern: Option<String>,
+    pub directories: Vec<String>,
+    pub line: usize,
+    pub column: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct Variable {
     pub name: String,
     pub value: String,
+    pub operator: AssignmentOperator,
     pub line: usize,
     pub column: usize,
+    pub operator_column: usize,
+    /// Column where the value starts, past the operator and any whitespace. The body of a
+    /// `define` block starts on the next line, so for those this is 1.
+    pub value_start_column: usize,
+}
+
+/// A syntax error that prevents the file from being parsed.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{message} at line {line}")]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, line: usize) -> Self {
+        Self {
+            message: message.into(),
+            line,
+        }
+    }
+
+    /// Reports the error as an `MK000` diagnostic, so that it can be output like any other
+    /// finding.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(
+            "MK000",
+            Severity::Error,
+            format!("Parse error: {}", self.message),
+            self.line,
+            1,
+        )
+    }
 }
 
-pub fn parse(content: &str) -> Result<Makefile> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentOperator {
+    /// `=`
+    Recursive,
+    /// `:=`
+    Simple,
+    /// `::=`
+    PosixSimple,
+    /// `?=`
+    Conditional,
+    /// `+=`
+    Append,
+    /// `!=`
+    Shell,
+}
+
+impl AssignmentOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssignmentOperator::Recursive => "=",
+            AssignmentOperator::Simple => ":=",
+            AssignmentOperator::PosixSimple => "::=",
+            AssignmentOperator::Conditional => "?=",
+            AssignmentOperator::Append => "+=",
+            AssignmentOperator::Shell => "!=",
+        }
+    }
+}
+
+pub fn parse(content: &str) -> Result<Makefile, ParseError> {
     let mut parser = Parser::new(content);
     parser.parse()
 }
@@ -51,12 +288,28 @@ impl<'a> Parser<'a> {
             makefile: Makefile {
                 rules: Vec::new(),
                 variables: HashMap::new(),
+                assignments: Vec::new(),
                 phonies: Vec::new(),
+                phony_lines: Vec::new(),
+                special_targets: HashMap::new(),
+                special_target_lines: HashMap::new(),
+                dotted_rule_lines: Vec::new(),
+                includes: Vec::new(),
+                comments: Vec::new(),
+                default_goal: None,
+                vpaths: Vec::new(),
+                exports: Vec::new(),
+                references: Vec::new(),
+                dangling_continuation: None,
+                suppressions: Vec::new(),
+                path: None,
+                included: Vec::new(),
+                missing_includes: Vec::new(),
             },
         }
     }
 
-    fn parse(&mut self) -> Result<Makefile> {
+    fn parse(&mut self) -> Result<Makefile, ParseError> {
         while self.current_line < self.lines.len() {
             let line = self.lines[self.current_line];
             let trimmed = line.trim_start();
@@ -66,15 +319,33 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
-            if trimmed.starts_with('.') || trimmed.starts_with('#') {
-                // Skip comments and special directives except .PHONY
+            if trimmed.starts_with('#') {
+                self.parse_comment(line);
+            } else if trimmed.starts_with('.') {
+                // Other dotted lines, such as suffix rules, aren't parsed into `rules`
                 if trimmed.starts_with(".PHONY:") {
                     self.parse_phony(line)?;
+                } else if trimmed.starts_with(".DEFAULT_GOAL") {
+                    self.parse_default_goal(trimmed);
+                } else if let Some((name, prerequisites)) = special_target(trimmed) {
+                    self.makefile.dotted_rule_lines.push(self.current_line + 1);
+                    self.parse_special_target(name, prerequisites);
                 } else {
+                    if self.is_rule_line(line) && !self.is_variable_assignment(line) {
+                        self.makefile.dotted_rule_lines.push(self.current_line + 1);
+                    }
                     self.current_line += 1;
                 }
+            } else if let Some((directive, optional)) = include_directive(trimmed) {
+                self.parse_include(line, directive, optional);
+            } else if let Some(definition) = define_directive(trimmed) {
+                self.parse_define(line, definition)?;
+            } else if is_vpath_directive(trimmed) {
+                self.parse_vpath(line);
             } else if self.is_variable_assignment(line) {
                 self.parse_variable(line)?;
+            } else if let Some(names) = export_directive(trimmed) {
+                self.parse_export(line, names);
             } else if self.is_rule_line(line) {
                 self.parse_rule()?;
             } else {
@@ -82,18 +353,180 @@ impl<'a> Parser<'a> {
             }
         }
 
+        self.makefile.suppressions = suppressions(&self.makefile.comments);
+        self.makefile.dangling_continuation = self
+            .lines
+            .iter()
+            .rposition(|line| !line.trim().is_empty())
+            .filter(|&index| {
+                // An even number of backslashes is escaped rather than a continuation
+                let line = self.lines[index].trim_end_matches('\r');
+                (line.len() - line.trim_end_matches('\\').len()) % 2 == 1
+            })
+            .map(|index| index + 1);
+        self.makefile.references = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !line.trim_start().starts_with('#'))
+            .flat_map(|(index, line)| find_references(line, index + 1))
+            .collect();
         Ok(self.makefile.clone())
     }
 
-    fn parse_phony(&mut self, line: &str) -> Result<()> {
-        let targets = line
-            .trim_start()
-            .trim_start_matches(".PHONY:")
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
+    fn parse_phony(&mut self, line: &str) -> Result<(), ParseError> {
+        let line_number = self.current_line + 1;
+        let mut names = line.trim_start().trim_start_matches(".PHONY:").to_string();
+
+        // Join continuation lines, as for variable values
+        while self.current_line + 1 < self.lines.len()
+            && self.lines[self.current_line].ends_with('\\')
+        {
+            names.pop();
+            names.push(' ');
+            self.current_line += 1;
+            names.push_str(self.lines[self.current_line]);
+        }
+
+        self.makefile
+            .phonies
+            .extend(names.split_whitespace().map(|s| s.to_string()));
+        self.makefile.phony_lines.push(line_number);
+        self.current_line += 1;
+        Ok(())
+    }
+
+    fn parse_special_target(&mut self, name: &str, prerequisites: &str) {
+        self.makefile
+            .special_targets
+            .entry(name.to_string())
+            .or_default()
+            .extend(split_words(prerequisites));
+        self.makefile
+            .special_target_lines
+            .entry(name.to_string())
+            .or_default()
+            .push(self.current_line + 1);
+        self.current_line += 1;
+    }
+
+    fn parse_default_goal(&mut self, trimmed: &str) {
+        if let Some((pos, operator)) = find_assignment(trimmed) {
+            let value = trimmed[pos + operator.as_str().len()..].trim();
+            self.makefile.default_goal = Some(value.to_string());
+        }
+        self.current_line += 1;
+    }
+
+    fn parse_comment(&mut self, line: &str) {
+        let column = line.len() - line.trim_start().len() + 1;
+
+        self.makefile.comments.push(Comment {
+            text: line.trim_start()[1..].to_string(),
+            line: self.current_line + 1,
+            column,
+        });
+        self.current_line += 1;
+    }
+
+    fn parse_include(&mut self, line: &str, directive: &str, optional: bool) {
+        let column = line.len() - line.trim_start().len() + 1;
+        let paths = line.trim_start()[directive.len()..]
+            .split('#')
+            .next()
+            .unwrap_or_default();
+
+        self.makefile.includes.push(Include {
+            paths: split_words(paths),
+            optional,
+            line: self.current_line + 1,
+            column,
+        });
+        self.current_line += 1;
+    }
+
+    fn parse_vpath(&mut self, line: &str) {
+        let column = line.len() - line.trim_start().len() + 1;
+        let arguments = line.trim_start()["vpath".len()..]
+            .split('#')
+            .next()
+            .unwrap_or_default();
+        let mut words = arguments.split_whitespace();
+
+        // `vpath` and `vpath pattern` clear search paths rather than add one
+        if let Some(pattern) = words.next() {
+            let directories: Vec<String> = words.flat_map(split_search_path).collect();
+            if !directories.is_empty() {
+                self.makefile.vpaths.push(Vpath {
+                    pattern: Some(pattern.to_string()),
+                    directories,
+                    line: self.current_line + 1,
+                    column,
+                });
+            }
+        }
+        self.current_line += 1;
+    }
+
+    /// Parses a multi-line `define NAME [op] ... endef` variable definition.
+    fn parse_define(&mut self, line: &str, definition: &str) -> Result<(), ParseError> {
+        let line_number = self.current_line + 1;
+        let column = line.len() - line.trim_start().len() + 1;
+        let definition = definition.trim();
+
+        let (name, operator) = [
+            AssignmentOperator::PosixSimple,
+            AssignmentOperator::Simple,
+            AssignmentOperator::Conditional,
+            AssignmentOperator::Append,
+            AssignmentOperator::Shell,
+            AssignmentOperator::Recursive,
+        ]
+        .into_iter()
+        .find_map(|operator| {
+            definition
+                .strip_suffix(operator.as_str())
+                .map(|name| (name.trim_end(), operator))
+        })
+        .unwrap_or((definition, AssignmentOperator::Recursive));
+        let name_column = line.find(name).map_or(column, |pos| pos + 1);
+
+        // Definitions may nest, so only the matching `endef` ends this one
+        let mut depth = 1;
+        let mut body = Vec::new();
+        self.current_line += 1;
+        while self.current_line < self.lines.len() {
+            let body_line = self.lines[self.current_line];
+            let trimmed = body_line.trim_start();
+            if define_directive(trimmed).is_some() {
+                depth += 1;
+            } else if trimmed == "endef" || trimmed.starts_with("endef ") {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            body.push(body_line);
+            self.current_line += 1;
+        }
+
+        if depth > 0 {
+            return Err(ParseError::new(
+                format!("'define {name}' is missing a matching 'endef'"),
+                line_number,
+            ));
+        }
+
+        self.add_variable(Variable {
+            name: name.to_string(),
+            value: body.join("\n"),
+            operator,
+            line: line_number,
+            column,
+            operator_column: name_column + name.len(),
+            value_start_column: 1,
+        });
 
-        self.makefile.phonies.extend(targets);
         self.current_line += 1;
         Ok(())
     }
@@ -104,33 +537,37 @@ impl<'a> Parser<'a> {
             return false;
         }
 
-        trimmed.contains("=") && !trimmed.contains(':')
+        find_assignment(trimmed).is_some()
     }
 
-    fn parse_variable(&mut self, line: &str) -> Result<()> {
-        let column = line.len() - line.trim_start().len() + 1;
-        let content = line.trim_start();
-
-        let sep = if content.contains(":=") {
-            ":="
-        } else if content.contains("?=") {
-            "?="
-        } else if content.contains("+=") {
-            "+="
-        } else {
-            "="
-        };
-
-        let parts: Vec<&str> = content.splitn(2, sep).collect();
-        if parts.len() != 2 {
-            bail!(
-                "Invalid variable assignment at line {}",
-                self.current_line + 1
-            );
+    fn parse_export(&mut self, line: &str, names: &str) {
+        let names = names.split('#').next().unwrap_or_default();
+        for name in names.split_whitespace() {
+            let column = name.as_ptr() as usize - line.as_ptr() as usize + 1;
+            self.makefile.exports.push(Export {
+                name: name.to_string(),
+                line: self.current_line + 1,
+                column,
+            });
         }
+        self.current_line += 1;
+    }
+
+    fn parse_variable(&mut self, line: &str) -> Result<(), ParseError> {
+        let line_number = self.current_line + 1;
+        let (exported, content) = strip_variable_modifiers(line.trim_start());
+        let column = line.len() - content.len() + 1;
 
-        let name = parts[0].trim().to_string();
-        let mut value = parts[1].trim().to_string();
+        let Some((operator_pos, operator)) = find_assignment(content) else {
+            return Err(ParseError::new("Invalid variable assignment", line_number));
+        };
+
+        let name = content[..operator_pos].trim().to_string();
+        let value_pos = operator_pos + operator.as_str().len();
+        let raw_value = &content[value_pos..];
+        let value_start_column =
+            column + value_pos + raw_value.len() - raw_value.trim_start().len();
+        let mut value = raw_value.trim().to_string();
 
         while self.current_line + 1 < self.lines.len()
             && self.lines[self.current_line].ends_with('\\')
@@ -140,27 +577,52 @@ impl<'a> Parser<'a> {
             value.push_str(self.lines[self.current_line].trim_start());
         }
 
-        self.makefile.variables.insert(
-            name.clone(),
-            Variable {
-                name,
-                value,
-                line: self.current_line + 1,
+        if exported {
+            self.makefile.exports.push(Export {
+                name: name.clone(),
+                line: line_number,
                 column,
-            },
-        );
+            });
+        }
+
+        if name == "VPATH" {
+            self.makefile.vpaths.push(Vpath {
+                pattern: None,
+                directories: split_search_path(&value),
+                line: line_number,
+                column,
+            });
+        }
+
+        self.add_variable(Variable {
+            name,
+            value,
+            operator,
+            line: line_number,
+            column,
+            operator_column: column + operator_pos,
+            value_start_column,
+        });
 
         self.current_line += 1;
         Ok(())
     }
 
+    fn add_variable(&mut self, variable: Variable) {
+        self.makefile.assignments.push(variable.clone());
+        self.makefile
+            .variables
+            .insert(variable.name.clone(), variable);
+    }
+
     fn is_rule_line(&self, line: &str) -> bool {
         let trimmed = line.trim();
         !trimmed.is_empty() && trimmed.contains(':') && !trimmed.starts_with('\t')
     }
 
-    fn parse_rule(&mut self) -> Result<()> {
+    fn parse_rule(&mut self) -> Result<(), ParseError> {
         let line = self.lines[self.current_line];
+        let line_number = self.current_line + 1;
         let column = line.len() - line.trim_start().len() + 1;
 
         let colon_pos = line.find(':').unwrap();
@@ -171,34 +633,39 @@ impl<'a> Parser<'a> {
             .map(|s| s.to_string())
             .collect();
 
+        let double_colon = line[colon_pos + 1..].starts_with(':');
+        let rest_start = colon_pos + if double_colon { 2 } else { 1 };
+        let (rest, inline) = split_inline_recipe(&line[rest_start..]);
+        let rest = rest.split('#').next().unwrap_or(rest);
+
+        // Target-specific variable assignments (`target: VAR = value`) have no prerequisites
+        let (prerequisites, order_only_prerequisites) = if rest.contains('=') {
+            (Vec::new(), Vec::new())
+        } else {
+            let (normal, order_only) = rest.split_once('|').unwrap_or((rest, ""));
+            (split_words(normal), split_words(order_only))
+        };
+
         let mut recipes = Vec::new();
+        if let Some(inline) = inline {
+            recipes.push(self.read_recipe(line.len() - inline.trim_start().len(), true));
+        }
+
+        let mut blank_lines = Vec::new();
+        // Blank lines only count as inside the rule once another recipe line follows
+        let mut pending_blank_lines = Vec::new();
         self.current_line += 1;
 
         while self.current_line < self.lines.len() {
             let recipe_line = self.lines[self.current_line];
 
             if recipe_line.starts_with('\t') || recipe_line.starts_with(' ') {
-                let mut command = recipe_line.trim_start().to_string();
-                let indentation =
-                    &recipe_line[..recipe_line.len() - recipe_line.trim_start().len()];
-
-                if command.starts_with('@') {
-                    command = command[1..].to_string();
-                }
-
-                if command.starts_with('-') {
-                    command = command[1..].to_string();
-                }
-
-                recipes.push(Recipe {
-                    command,
-                    line: self.current_line + 1,
-                    column: 1,
-                    indentation: indentation.to_string(),
-                });
-
+                let indentation = recipe_line.len() - recipe_line.trim_start().len();
+                blank_lines.append(&mut pending_blank_lines);
+                recipes.push(self.read_recipe(indentation, false));
                 self.current_line += 1;
             } else if recipe_line.trim().is_empty() {
+                pending_blank_lines.push(self.current_line + 1);
                 self.current_line += 1;
             } else {
                 break;
@@ -207,11 +674,450 @@ impl<'a> Parser<'a> {
 
         self.makefile.rules.push(Rule {
             targets,
+            prerequisites,
+            order_only_prerequisites,
+            double_colon,
             recipes,
-            line: self.current_line + 1,
+            blank_lines,
+            doc: self.doc_comment(line_number),
+            line: line_number,
             column,
         });
 
         Ok(())
     }
+
+    /// Reads the recipe starting at byte `start` of the current line, which holds its
+    /// prefix and command, joining continuation lines. Leaves `current_line` on the last
+    /// line of the command.
+    fn read_recipe(&mut self, start: usize, inline: bool) -> Recipe {
+        let recipe_line = self.lines[self.current_line];
+        let written = &recipe_line[start..];
+
+        // Prefixes may be combined in any order, e.g. `-@cmd` or `@-cmd`
+        let body = written.trim_start_matches(['@', '-', '+', ' ', '\t']);
+        let prefix = &written[..written.len() - body.len()];
+        let mut command = body.to_string();
+
+        let line = self.current_line + 1;
+        while command.ends_with('\\') && self.current_line + 1 < self.lines.len() {
+            command.pop();
+            self.current_line += 1;
+            command.push_str(self.lines[self.current_line].trim_start());
+        }
+
+        Recipe {
+            command,
+            line,
+            end_line: self.current_line + 1,
+            column: 1,
+            indentation: recipe_line[..start].to_string(),
+            inline,
+            prefix: prefix.to_string(),
+            silent: prefix.contains('@'),
+            ignore_error: prefix.contains('-'),
+            always_run: prefix.contains('+'),
+        }
+    }
+
+    /// Joins the consecutive `##` comments ending on the line before `line`.
+    fn doc_comment(&self, line: usize) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut expected = line - 1;
+        for comment in self.makefile.comments.iter().rev() {
+            match comment.text.strip_prefix('#') {
+                Some(text) if comment.line == expected => {
+                    lines.push(text.trim());
+                    expected -= 1;
+                }
+                _ => break,
+            }
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+}
+
+/// Locates the assignment operator of a variable assignment line, returning its byte
+/// offset. Lines where a `:` precedes the operator are rules (possibly with a
+/// target-specific variable) rather than assignments.
+fn find_assignment(line: &str) -> Option<(usize, AssignmentOperator)> {
+    let eq = line.find('=')?;
+    let before = &line[..eq];
+
+    let (start, operator) = if before.ends_with("::") {
+        (eq - 2, AssignmentOperator::PosixSimple)
+    } else if before.ends_with(':') {
+        (eq - 1, AssignmentOperator::Simple)
+    } else if before.ends_with('?') {
+        (eq - 1, AssignmentOperator::Conditional)
+    } else if before.ends_with('+') {
+        (eq - 1, AssignmentOperator::Append)
+    } else if before.ends_with('!') {
+        (eq - 1, AssignmentOperator::Shell)
+    } else {
+        (eq, AssignmentOperator::Recursive)
+    };
+
+    let name = &line[..start];
+    if name.trim().is_empty() || name.contains(':') {
+        return None;
+    }
+
+    Some((start, operator))
+}
+
+/// GNU Make's built-in functions.
+const FUNCTIONS: &[&str] = &[
+    "subst",
+    "patsubst",
+    "strip",
+    "findstring",
+    "filter",
+    "filter-out",
+    "sort",
+    "word",
+    "wordlist",
+    "words",
+    "firstword",
+    "lastword",
+    "dir",
+    "notdir",
+    "suffix",
+    "basename",
+    "addsuffix",
+    "addprefix",
+    "join",
+    "wildcard",
+    "realpath",
+    "abspath",
+    "error",
+    "warning",
+    "info",
+    "shell",
+    "origin",
+    "flavor",
+    "foreach",
+    "if",
+    "or",
+    "and",
+    "intcmp",
+    "call",
+    "eval",
+    "file",
+    "value",
+    "let",
+    "guile",
+];
+
+/// Functions whose first argument names a variable.
+const NAME_FUNCTIONS: &[&str] = &["call", "value", "origin", "flavor"];
+
+fn is_automatic_variable(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = chars.next();
+    matches!(first, Some('@' | '<' | '^' | '+' | '?' | '*' | '|' | '%'))
+        && matches!(chars.next(), None | Some('D' | 'F'))
+        && chars.next().is_none()
+}
+
+/// Names of the variables that `text`, such as a variable value, references, including in
+/// nested references.
+pub fn referenced_variables(text: &str) -> Vec<String> {
+    find_references(text, 1)
+        .into_iter()
+        .filter(|reference| reference.kind == ReferenceKind::Variable)
+        .map(|reference| reference.name)
+        .collect()
+}
+
+/// Finds the references on one line, including nested ones.
+fn find_references(line: &str, line_number: usize) -> Vec<VarRef> {
+    let bytes = line.as_bytes();
+    let mut references = Vec::new();
+    let reference = |name: &str, kind, offset: usize| VarRef {
+        name: name.to_string(),
+        kind,
+        line: line_number,
+        column: offset + 1,
+    };
+
+    let directive = line.trim_start();
+    if let Some(name) = ["ifdef ", "ifndef "]
+        .iter()
+        .find_map(|keyword| directive.strip_prefix(keyword))
+        .and_then(|rest| rest.split_whitespace().next())
+    {
+        let offset = name.as_ptr() as usize - line.as_ptr() as usize;
+        references.push(reference(name, ReferenceKind::Variable, offset));
+    }
+
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        if bytes[i] != b'$' {
+            i += 1;
+            continue;
+        }
+
+        let (open, close) = match bytes[i + 1] {
+            b'$' => {
+                i += 2;
+                continue;
+            }
+            b'(' => (b'(', b')'),
+            b'{' => (b'{', b'}'),
+            _ => {
+                // Single-character reference such as `$@` or `$x`
+                let Some(name) = line[i + 1..].chars().next() else {
+                    break;
+                };
+                let name = name.to_string();
+                let kind = if is_automatic_variable(&name) {
+                    ReferenceKind::Automatic
+                } else {
+                    ReferenceKind::Variable
+                };
+                if kind == ReferenceKind::Automatic
+                    || name.chars().all(|c| c.is_alphanumeric() || c == '_')
+                {
+                    references.push(reference(&name, kind, i));
+                }
+                i += 1 + name.len();
+                continue;
+            }
+        };
+
+        let mut depth = 0;
+        let mut end = None;
+        for (j, &byte) in bytes.iter().enumerate().skip(i + 1) {
+            if byte == open {
+                depth += 1;
+            } else if byte == close {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(j);
+                    break;
+                }
+            }
+        }
+        let Some(end) = end else {
+            break;
+        };
+
+        let inner = &line[i + 2..end];
+        let word_end = inner
+            .find(|c: char| c.is_whitespace())
+            .unwrap_or(inner.len());
+        let word = &inner[..word_end];
+
+        if word_end < inner.len() && FUNCTIONS.contains(&word) {
+            references.push(reference(word, ReferenceKind::Function, i));
+            if NAME_FUNCTIONS.contains(&word) {
+                let argument = inner[word_end..].trim_start();
+                let name = argument.split(',').next().unwrap_or_default().trim_end();
+                if !name.is_empty() && !name.contains('$') {
+                    let offset = name.as_ptr() as usize - line.as_ptr() as usize;
+                    references.push(reference(name, ReferenceKind::Variable, offset));
+                }
+            }
+        } else {
+            // Substitution references like `$(OBJS:.o=.c)` name the variable before the `:`
+            let name = top_level_prefix(inner, ':');
+            let kind = if is_automatic_variable(name) {
+                ReferenceKind::Automatic
+            } else {
+                ReferenceKind::Variable
+            };
+            if !name.is_empty() {
+                references.push(reference(name, kind, i));
+            }
+        }
+
+        i += 2;
+    }
+
+    references
+}
+
+/// The part of `text` before the first `separator` that isn't nested in parentheses or
+/// braces.
+fn top_level_prefix(text: &str, separator: char) -> &str {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth = depth.saturating_sub(1),
+            _ if c == separator && depth == 0 => return &text[..i],
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Collects the line ranges of `# rumk: disable-next-line IDS`, `# rumk: disable IDS` and
+/// `# rumk: enable IDS` comments. Without ids, `disable` applies to every rule and `enable`
+/// ends every open range. Repeated disables and enables without a matching disable are
+/// ignored, and ranges still open at the end of the file run to its end.
+fn suppressions(comments: &[Comment]) -> Vec<Suppression> {
+    let mut ranges = Vec::new();
+    let mut open: Vec<(Option<String>, usize)> = Vec::new();
+
+    for comment in comments {
+        let Some(directive) = comment.text.trim().strip_prefix("rumk:") else {
+            continue;
+        };
+        let mut words = directive
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty());
+        let Some(action) = words.next() else {
+            continue;
+        };
+        let mut rules: Vec<Option<String>> = words.map(|id| Some(id.to_string())).collect();
+        if rules.is_empty() {
+            rules.push(None);
+        }
+
+        match action {
+            "disable-next-line" => {
+                ranges.extend(rules.into_iter().map(|rule| Suppression {
+                    rule,
+                    start_line: comment.line + 1,
+                    end_line: Some(comment.line + 1),
+                }));
+            }
+            "disable" => {
+                for rule in rules {
+                    if !open.iter().any(|(open_rule, _)| *open_rule == rule) {
+                        open.push((rule, comment.line));
+                    }
+                }
+            }
+            "enable" => {
+                open.retain(|(rule, start_line)| {
+                    let closed = rules == [None] || rules.contains(rule);
+                    if closed {
+                        ranges.push(Suppression {
+                            rule: rule.clone(),
+                            start_line: *start_line,
+                            end_line: Some(comment.line),
+                        });
+                    }
+                    !closed
+                });
+            }
+            _ => {}
+        }
+    }
+
+    ranges.extend(open.into_iter().map(|(rule, start_line)| Suppression {
+        rule,
+        start_line,
+        end_line: None,
+    }));
+    ranges
+}
+
+/// Strips the `export` and `override` keywords that may precede an assignment, returning
+/// whether the variable is exported.
+fn strip_variable_modifiers(mut text: &str) -> (bool, &str) {
+    let mut exported = false;
+    loop {
+        let Some((keyword, rest)) = ["export", "override"].into_iter().find_map(|keyword| {
+            let rest = text.strip_prefix(keyword)?;
+            let name = rest.trim_start();
+            // `export = value` assigns a variable named `export`
+            (rest.starts_with([' ', '\t']) && !name.starts_with(['=', ':', '?', '+', '!']))
+                .then_some((keyword, name))
+        }) else {
+            return (exported, text);
+        };
+        exported |= keyword == "export";
+        text = rest;
+    }
+}
+
+/// Recognizes an `export` directive without an assignment, returning the listed names.
+fn export_directive(trimmed: &str) -> Option<&str> {
+    let names = trimmed.strip_prefix("export")?;
+    (names.is_empty() || names.starts_with([' ', '\t'])).then_some(names)
+}
+
+/// Recognizes a `.NAME:` declaration whose name, like Make's special targets, is all
+/// uppercase letters and underscores, returning the name and the prerequisite text.
+fn special_target(trimmed: &str) -> Option<(&str, &str)> {
+    let (name, rest) = trimmed.split_once(':')?;
+    let name = name.trim_end();
+    let valid = name.len() > 1
+        && name[1..]
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c == '_');
+    // `.NAME := value` is an assignment, and `.NAME::` isn't a special target either
+    (valid && !rest.starts_with([':', '='])).then_some((name, rest.split(';').next()?))
+}
+
+/// Splits the text after a rule's colon at the `;` that starts an inline recipe, unless a
+/// comment starts first. Escaped characters don't count.
+fn split_inline_recipe(rest: &str) -> (&str, Option<&str>) {
+    let mut escaped = false;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '#' if !escaped => return (rest, None),
+            ';' if !escaped => return (&rest[..index], Some(&rest[index + 1..])),
+            _ => {}
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    (rest, None)
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    text.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+/// Splits a search path on colons and whitespace, as Make does for `VPATH` and `vpath`.
+fn split_search_path(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ':' || c.is_whitespace())
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| dir.to_string())
+        .collect()
+}
+
+/// Recognizes a `define` directive, optionally preceded by `export` or `override`,
+/// returning the text that follows the keyword.
+fn define_directive(trimmed: &str) -> Option<&str> {
+    let trimmed = ["export", "override"]
+        .into_iter()
+        .find_map(|prefix| {
+            trimmed
+                .strip_prefix(prefix)
+                .filter(|rest| rest.starts_with([' ', '\t']))
+        })
+        .map_or(trimmed, str::trim_start);
+
+    trimmed
+        .strip_prefix("define")
+        .filter(|rest| rest.starts_with([' ', '\t']))
+}
+
+fn is_vpath_directive(trimmed: &str) -> bool {
+    trimmed == "vpath"
+        || trimmed
+            .strip_prefix("vpath")
+            .is_some_and(|rest| rest.starts_with([' ', '\t']) && find_assignment(trimmed).is_none())
+}
+
+/// Recognizes `include`, `-include` and `sinclude` directives, returning the directive
+/// keyword and whether it is optional.
+fn include_directive(trimmed: &str) -> Option<(&'static str, bool)> {
+    [("include", false), ("-include", true), ("sinclude", true)]
+        .into_iter()
+        .find(|(directive, _)| {
+            trimmed
+                .strip_prefix(directive)
+                .is_some_and(|rest| rest.starts_with([' ', '\t']))
+        })
 }
@@ -1,6 +1,8 @@
 use crate::diagnostic::Diagnostic;
 use crate::parser::Makefile;
 use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub mod best_practices;
 pub mod style;
@@ -12,9 +14,69 @@ pub trait Rule: Send + Sync {
     fn description(&self) -> &'static str;
     fn category(&self) -> RuleCategory;
     fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic>;
+
+    /// Whether the rule only runs when a config file enables it explicitly, rather than
+    /// whenever a config file is used.
+    fn opt_in(&self) -> bool {
+        false
+    }
+
+    /// Whether some of the rule's diagnostics carry a fix.
+    fn fixable(&self) -> bool {
+        false
+    }
+
+    /// The keys accepted in the `options` table of the rule's configuration entry, with
+    /// their current values as defaults.
+    fn options(&self) -> Vec<OptionSpec> {
+        Vec::new()
+    }
+
+    /// Applies the `options` table of the rule's configuration entry.
+    fn configure(&mut self, _options: &HashMap<String, toml::Value>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Type of a rule option's value in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionKind {
+    Boolean,
+    Integer,
+    Float,
+    String,
+    StringArray,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Describes a key accepted in a rule's `options` table.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OptionSpec {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub kind: OptionKind,
+    pub default: toml::Value,
+    pub help: &'static str,
+}
+
+impl OptionSpec {
+    pub fn new(
+        name: &'static str,
+        kind: OptionKind,
+        default: impl Into<toml::Value>,
+        help: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            kind,
+            default: default.into(),
+            help,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RuleCategory {
     Syntax,
     Style,
@@ -24,38 +86,186 @@ pub enum RuleCategory {
 pub fn get_rule_explanation(rule_id: &str) -> Result<String> {
     let all_rules = get_all_rules();
 
-    for rule in all_rules {
+    for rule in &all_rules {
         if rule.id() == rule_id {
-            return Ok(format!(
-                "Rule: {}\nCategory: {:?}\nDescription: {}\n\n{}",
-                rule.id(),
-                rule.category(),
-                rule.name(),
-                rule.description()
-            ));
+            return Ok(explanation(rule.as_ref()));
         }
     }
 
-    bail!("Unknown rule: {}", rule_id)
+    match closest_rule_id(rule_id) {
+        Some(id) => bail!("Unknown rule: {} (did you mean {}?)", rule_id, id),
+        None => bail!("Unknown rule: {}", rule_id),
+    }
+}
+
+/// The rule id closest to `rule_id`, if it's close enough to plausibly be a typo.
+pub fn closest_rule_id(rule_id: &str) -> Option<&'static str> {
+    get_all_rules()
+        .iter()
+        .map(|rule| (levenshtein(rule_id, rule.id()), rule.id()))
+        .min_by_key(|(distance, _)| *distance)
+        .filter(|(distance, _)| *distance <= 2)
+        .map(|(_, id)| id)
+}
+
+/// Number of single-character insertions, deletions and substitutions turning `a` into `b`.
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// The conditional branches each line is in, outermost first, as pairs of the line number
+/// of the `if` directive and the number of `else` directives seen since.
+pub(crate) fn conditional_contexts(content: &str) -> Vec<Vec<(usize, usize)>> {
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    let mut contexts = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let keyword = if line.starts_with('\t') {
+            None
+        } else {
+            line.split_whitespace().next()
+        };
+        match keyword {
+            Some(keyword) if keyword.starts_with("ifeq") || keyword.starts_with("ifneq") => {
+                stack.push((index + 1, 0));
+            }
+            Some("ifdef" | "ifndef") => stack.push((index + 1, 0)),
+            Some("else") => {
+                if let Some((_, branch)) = stack.last_mut() {
+                    *branch += 1;
+                }
+            }
+            Some("endif") => {
+                stack.pop();
+            }
+            _ => {}
+        }
+        contexts.push(stack.clone());
+    }
+
+    contexts
+}
+
+/// Explanations of every rule, as plain text or as a Markdown document.
+pub fn get_all_explanations(markdown: bool) -> String {
+    let explanations: Vec<String> = get_all_rules()
+        .iter()
+        .map(|rule| {
+            if markdown {
+                markdown_explanation(rule.as_ref())
+            } else {
+                explanation(rule.as_ref())
+            }
+        })
+        .collect();
+
+    if markdown {
+        format!("# Rules\n\n{}", explanations.join("\n\n"))
+    } else {
+        explanations.join("\n\n---\n\n")
+    }
+}
+
+fn explanation(rule: &dyn Rule) -> String {
+    format!(
+        "Rule: {}\nCategory: {:?}\nDescription: {}\n\n{}",
+        rule.id(),
+        rule.category(),
+        rule.name(),
+        rule.description()
+    )
+}
+
+fn markdown_explanation(rule: &dyn Rule) -> String {
+    format!(
+        "## {}: {}\n\n**Category:** {:?}\n\n{}",
+        rule.id(),
+        rule.name(),
+        rule.category(),
+        rule.description()
+    )
 }
 
 pub fn get_all_rules() -> Vec<Box<dyn Rule>> {
     vec![
         Box::new(syntax::TabInRecipe),
-        Box::new(syntax::InvalidVariableSyntax),
+        Box::new(syntax::InvalidVariableSyntax::new()),
+        Box::new(syntax::TabOutsideRecipe),
+        Box::new(syntax::AutomaticVariableAssignment),
+        Box::new(syntax::DanglingContinuation),
+        Box::new(syntax::DoubledOperator),
+        Box::new(syntax::RecursiveSelfReference),
+        Box::new(syntax::UnknownSpecialTarget),
         Box::new(style::LineLength::new(120)),
         Box::new(style::VariableNaming::new(style::NamingStyle::Upper)),
         Box::new(style::TargetNaming::new(style::NamingStyle::Lower)),
+        Box::new(style::VariableReferenceStyle::new(
+            style::ReferenceStyle::Paren,
+        )),
+        Box::new(style::DuplicatePrerequisite),
+        Box::new(style::TodoComment::new()),
+        Box::new(style::AssignmentSpacingStyle::new(
+            style::AssignmentSpacing::Spaced,
+        )),
+        Box::new(style::EchoPrefixConsistency::new()),
+        Box::new(style::SilentTarget::new()),
+        Box::new(style::BlankLineInRecipe),
+        Box::new(style::MultiplePhony),
+        Box::new(style::LongPrerequisiteList::new()),
+        Box::new(style::UndocumentedPhony),
+        Box::new(style::EmbeddedTab),
+        Box::new(style::MissingFinalNewline),
+        Box::new(style::LongRecipeCommand::new()),
+        Box::new(style::EchoProgress::new()),
+        Box::new(style::ShadowedVariable),
         Box::new(best_practices::MissingPhony),
         Box::new(best_practices::HardcodedPath),
+        Box::new(best_practices::DangerousRm),
+        Box::new(best_practices::RecursiveMake),
+        Box::new(best_practices::EmptyRecipe::new()),
+        Box::new(best_practices::UndefinedPhony::new()),
+        Box::new(best_practices::BacktickSubstitution),
+        Box::new(best_practices::ImplicitDefaultGoal),
+        Box::new(best_practices::ShellWithoutFlags),
+        Box::new(best_practices::ToolVariableOverride::new()),
+        Box::new(best_practices::Bashism),
+        Box::new(best_practices::UndefinedPrerequisite::new()),
+        Box::new(best_practices::PermissiveChmod),
+        Box::new(best_practices::ExportUndefined),
+        Box::new(best_practices::UnusedVariable),
+        Box::new(best_practices::EagerShell::new()),
+        Box::new(best_practices::IgnoredError::new()),
+        Box::new(best_practices::RequiredInclude),
+        Box::new(best_practices::Sudo),
+        Box::new(best_practices::MissingOutputDirectory),
+        Box::new(best_practices::GnuFunction::new()),
+        Box::new(best_practices::MisplacedDependency),
+        Box::new(best_practices::IncludeCycle::new()),
+        Box::new(best_practices::MissingIncludeFile),
     ]
 }
 
 pub fn get_default_rules() -> Vec<Box<dyn Rule>> {
     vec![
         Box::new(syntax::TabInRecipe),
-        Box::new(syntax::InvalidVariableSyntax),
+        Box::new(syntax::InvalidVariableSyntax::new()),
+        Box::new(syntax::TabOutsideRecipe),
         Box::new(style::LineLength::new(120)),
         Box::new(best_practices::MissingPhony),
+        Box::new(best_practices::DangerousRm),
+        Box::new(best_practices::RecursiveMake),
+        Box::new(best_practices::UndefinedPhony::new()),
     ]
 }
@@ -1,6 +1,12 @@
-use crate::diagnostic::{Diagnostic, Severity};
-use crate::parser::Makefile;
-use crate::rules::{Rule, RuleCategory};
+use crate::diagnostic::{Diagnostic, Edit, Fix, Severity};
+use crate::includes;
+use crate::parser::{self, AssignmentOperator, Makefile, Recipe, ReferenceKind};
+use crate::rules::{conditional_contexts, OptionKind, OptionSpec, Rule, RuleCategory};
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 
 pub struct MissingPhony;
 
@@ -22,22 +28,37 @@ impl Rule for MissingPhony {
         RuleCategory::BestPractices
     }
 
-    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
         let common_phony_targets = ["all", "clean", "test", "check", "install", "build", "help"];
+        let phony_declaration = find_phony_declaration(content);
 
         for rule in &makefile.rules {
             for target in &rule.targets {
-                if common_phony_targets.contains(&target.as_str())
-                    && !makefile.phonies.contains(target)
-                {
-                    diagnostics.push(Diagnostic::new(
-                        self.id(),
-                        Severity::Warning,
-                        format!("Target '{target}' should be declared .PHONY"),
-                        rule.line,
-                        rule.column,
-                    ));
+                if common_phony_targets.contains(&target.as_str()) && !makefile.is_phony(target) {
+                    let edit = match phony_declaration {
+                        Some((line, end_column)) => {
+                            Edit::new(line, end_column, line, end_column, format!(" {target}"))
+                        }
+                        None => {
+                            Edit::new(rule.line, 1, rule.line, 1, format!(".PHONY: {target}\n"))
+                        }
+                    };
+
+                    diagnostics.push(
+                        Diagnostic::new(
+                            self.id(),
+                            Severity::Warning,
+                            format!("Target '{target}' should be declared .PHONY"),
+                            rule.line,
+                            rule.column,
+                        )
+                        .with_fix(Fix::new(format!("Declare '{target}' as .PHONY")).add_edit(edit)),
+                    );
                 }
             }
         }
@@ -46,6 +67,45 @@ impl Rule for MissingPhony {
     }
 }
 
+/// Names of the variables that the value of `PREFIX` references, directly or through
+/// other variables.
+fn prefix_sources(makefile: &Makefile) -> HashSet<String> {
+    let mut sources = HashSet::new();
+    let mut pending = vec!["PREFIX".to_string()];
+
+    while let Some(name) = pending.pop() {
+        for variable in makefile.assignments_of(&name) {
+            for referenced in parser::referenced_variables(&variable.value) {
+                if sources.insert(referenced.clone()) {
+                    pending.push(referenced);
+                }
+            }
+        }
+    }
+
+    sources
+}
+
+/// Returns the line and the column just past the target list of the first `.PHONY:`
+/// declaration. For a declaration continued with `\`, that is on its last line.
+fn find_phony_declaration(content: &str) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with(".PHONY:"))?;
+    let end = (start..lines.len())
+        .find(|&idx| !lines[idx].ends_with('\\'))
+        .unwrap_or(lines.len() - 1);
+
+    let line = lines[end];
+    let declaration = line.split('#').next().unwrap_or(line).trim_end();
+    let declaration = declaration
+        .strip_suffix('\\')
+        .unwrap_or(declaration)
+        .trim_end();
+    Some((end + 1, declaration.len() + 1))
+}
+
 pub struct HardcodedPath;
 
 impl Rule for HardcodedPath {
@@ -59,38 +119,210 @@ impl Rule for HardcodedPath {
 
     fn description(&self) -> &'static str {
         "Hardcoded absolute paths reduce portability and make the Makefile less flexible. \
-         Use variables or relative paths instead."
+         Use variables or relative paths instead. The value of `PREFIX` itself is exempt. An \
+         unsafe fix rewrites paths under `/usr/local` in variable values as `$(PREFIX)` \
+         references, except in the variables `PREFIX` is defined from."
     }
 
     fn category(&self) -> RuleCategory {
         RuleCategory::BestPractices
     }
 
-    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+        let prefix_sources = prefix_sources(makefile);
+
+        for variable in &makefile.assignments {
+            // Defining the prefix itself is where its absolute path belongs
+            if variable.name == "PREFIX" {
+                continue;
+            }
+            let Some((_, path)) = find_absolute_path(&variable.value) else {
+                continue;
+            };
+            let message = format!(
+                "Variable '{}' contains hardcoded absolute path '{path}'",
+                variable.name
+            );
 
-        for variable in makefile.variables.values() {
-            if contains_absolute_path(&variable.value) {
+            // Paths in the part of the value on the assignment line can be rewritten in place
+            let on_line = lines
+                .get(variable.line - 1)
+                .and_then(|line| line.get(variable.value_start_column - 1..))
+                .and_then(|value| find_absolute_path(value.trim_end_matches('\\')));
+            let Some((offset, path)) = on_line.filter(|_| variable.value_start_column > 1) else {
                 diagnostics.push(Diagnostic::new(
                     self.id(),
                     Severity::Warning,
-                    format!(
-                        "Variable '{}' contains hardcoded absolute path",
-                        variable.name
-                    ),
+                    message,
                     variable.line,
                     variable.column,
                 ));
+                continue;
+            };
+
+            let column = variable.value_start_column + offset;
+            let mut diagnostic =
+                Diagnostic::new(self.id(), Severity::Warning, message, variable.line, column)
+                    .with_span(variable.line, column + path.len());
+            // Only paths under the conventional prefix are installation paths; others such
+            // as `/bin/sh` name system files
+            let relative = path
+                .strip_prefix("/usr/local")
+                .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+                // Referencing PREFIX from a variable it is defined from would make it
+                // reference itself
+                .filter(|_| !prefix_sources.contains(&variable.name));
+            if let Some(relative) = relative {
+                // `PREFIX` may be undefined, so the rewritten value can differ
+                diagnostic = diagnostic.with_fix(
+                    Fix::new("Use a $(PREFIX) reference")
+                        .as_unsafe()
+                        .add_edit(Edit::new(
+                            variable.line,
+                            column,
+                            variable.line,
+                            column + path.len(),
+                            format!("$(PREFIX){relative}"),
+                        )),
+                );
+            }
+            diagnostics.push(diagnostic);
+        }
+
+        for rule in &makefile.rules {
+            for recipe in &rule.recipes {
+                // Report the first path at its physical position, which may be on a
+                // continuation line
+                let found = (recipe.line..=recipe.end_line).find_map(|line_num| {
+                    let line = lines.get(line_num - 1)?;
+                    let (offset, path) = find_absolute_path(line.trim_end_matches('\\'))?;
+                    Some((line_num, offset + 1, path))
+                });
+
+                if let Some((line, column, path)) = found {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            self.id(),
+                            Severity::Warning,
+                            format!("Recipe contains hardcoded absolute path '{path}'"),
+                            line,
+                            column,
+                        )
+                        .with_span(line, column + path.len()),
+                    );
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// Byte offset of a recipe's command within its first physical line, past the
+/// indentation and any `@`/`-`/`+` prefixes.
+fn command_offset(recipe: &Recipe) -> usize {
+    recipe.indentation.len() + recipe.prefix.len()
+}
+
+/// Splits `text` into whitespace-separated words with their byte offsets, keeping
+/// whitespace inside `$(...)` and `${...}` references, e.g. `$(addsuffix /lib,$(DIRS))`,
+/// within a single word.
+fn split_words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut depth: usize = 0;
+    let mut previous = None;
+
+    for (index, c) in text.char_indices() {
+        if c.is_whitespace() && depth == 0 {
+            if let Some(start) = start.take() {
+                words.push((start, &text[start..index]));
+            }
+        } else {
+            start.get_or_insert(index);
+            match c {
+                '(' | '{' if depth > 0 || previous == Some('$') => depth += 1,
+                ')' | '}' => depth = depth.saturating_sub(1),
+                _ => {}
             }
         }
+        previous = Some(c);
+    }
+    if let Some(start) = start {
+        words.push((start, &text[start..]));
+    }
+
+    words
+}
+
+/// Finds the first word that is an absolute Unix or Windows path, returning its byte
+/// offset and text. Words starting with a variable reference, like `$(PREFIX)/lib`, are
+/// variable-driven and never count, whatever follows the reference.
+fn find_absolute_path(text: &str) -> Option<(usize, &str)> {
+    split_words(text)
+        .into_iter()
+        .filter(|(_, word)| !word.starts_with('$'))
+        .find(|(_, word)| {
+            (word.starts_with('/') && word.len() > 1 && !word.starts_with("//"))
+                || (word.len() > 2
+                    && word.chars().nth(1) == Some(':')
+                    && word.chars().nth(2) == Some('\\'))
+        })
+}
+
+static RM_RECURSIVE_VARIABLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|[\s;&|(])rm\s+((?:-\S+\s+)+)(\$\(\w+\)|\$\{\w+\}|\$\$?\w+)").unwrap()
+});
+
+pub struct DangerousRm;
+
+impl Rule for DangerousRm {
+    fn id(&self) -> &'static str {
+        "MK207"
+    }
+
+    fn name(&self) -> &'static str {
+        "Recursive rm on an unquoted variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "`rm -rf $(VAR)` removes whatever the variable expands to. If the variable is empty \
+         or unset, paths like `$(VAR)/` collapse to `/`. Quote the path and guard against \
+         an empty value, e.g. `test -n \"$(VAR)\" && rm -rf \"$(VAR)/\"`."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
 
         for rule in &makefile.rules {
             for recipe in &rule.recipes {
-                if contains_absolute_path(&recipe.command) {
+                for captures in RM_RECURSIVE_VARIABLE.captures_iter(&recipe.command) {
+                    let recursive = captures[1].split_whitespace().any(|flag| {
+                        flag == "--recursive"
+                            || (!flag.starts_with("--") && flag.contains(['r', 'R']))
+                    });
+                    if !recursive {
+                        continue;
+                    }
+
+                    let variable = &captures[2];
                     diagnostics.push(Diagnostic::new(
                         self.id(),
                         Severity::Warning,
-                        "Recipe contains hardcoded absolute path",
+                        format!(
+                            "Recursive rm on unquoted variable '{variable}'; use \"{variable}/\" \
+                             with a guard against an empty value"
+                        ),
                         recipe.line,
                         recipe.column,
                     ));
@@ -102,11 +334,1806 @@ impl Rule for HardcodedPath {
     }
 }
 
-fn contains_absolute_path(text: &str) -> bool {
-    text.split_whitespace().any(|word| {
-        (word.starts_with('/') && word.len() > 1 && !word.starts_with("//"))
-            || (word.len() > 2
-                && word.chars().nth(1) == Some(':')
-                && word.chars().nth(2) == Some('\\'))
-    })
+pub struct RecursiveMake;
+
+impl Rule for RecursiveMake {
+    fn id(&self) -> &'static str {
+        "MK208"
+    }
+
+    fn name(&self) -> &'static str {
+        "Use $(MAKE) for recursive make"
+    }
+
+    fn description(&self) -> &'static str {
+        "Recursive invocations should use `$(MAKE)` instead of `make` so that flags such as \
+         `-j` (job server) and `-n` (dry run) propagate to the sub-make."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            for recipe in &rule.recipes {
+                if recipe.command != "make" && !recipe.command.starts_with("make ") {
+                    continue;
+                }
+
+                let column = command_offset(recipe) + 1;
+
+                // `$(MAKE)` also runs under `make -n` and inherits flags, changing behavior
+                let fix = Fix::new("Replace `make` with `$(MAKE)`")
+                    .as_unsafe()
+                    .add_edit(Edit::new(
+                        recipe.line,
+                        column,
+                        recipe.line,
+                        column + "make".len(),
+                        "$(MAKE)",
+                    ));
+
+                diagnostics.push(
+                    Diagnostic::new(
+                        self.id(),
+                        Severity::Warning,
+                        "Recursive make should use $(MAKE) instead of make",
+                        recipe.line,
+                        column,
+                    )
+                    .with_fix(fix),
+                );
+            }
+        }
+
+        diagnostics
+    }
+}
+
+pub struct EmptyRecipe {
+    severity: Severity,
+}
+
+impl EmptyRecipe {
+    pub fn new() -> Self {
+        Self {
+            severity: Severity::Warning,
+        }
+    }
+}
+
+impl Default for EmptyRecipe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for EmptyRecipe {
+    fn id(&self) -> &'static str {
+        "MK209"
+    }
+
+    fn name(&self) -> &'static str {
+        "Rule with prerequisites but no recipe"
+    }
+
+    fn description(&self) -> &'static str {
+        "A file target with prerequisites but no recipe is often a recipe that was \
+         accidentally dedented or split off by a blank line. Phony targets, double-colon \
+         rules and pattern rules are exempt. Set the `as_info` option to report these \
+         as informational."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            if !rule.recipes.is_empty() || rule.prerequisites.is_empty() || rule.double_colon {
+                continue;
+            }
+
+            let exempt = rule.targets.iter().any(|target| {
+                makefile.is_phony(target) || target.starts_with('.') || target.contains('%')
+            });
+            if exempt {
+                continue;
+            }
+
+            diagnostics.push(Diagnostic::new(
+                self.id(),
+                self.severity,
+                format!(
+                    "Target '{}' has prerequisites but no recipe",
+                    rule.targets.join(" ")
+                ),
+                rule.line,
+                rule.column,
+            ));
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "as_info",
+            OptionKind::Boolean,
+            self.severity == Severity::Info,
+            "Report empty recipes as info instead of warnings",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(as_info) = options.get("as_info") {
+            let Some(as_info) = as_info.as_bool() else {
+                bail!("option 'as_info' must be a boolean");
+            };
+            self.severity = if as_info {
+                Severity::Info
+            } else {
+                Severity::Warning
+            };
+        }
+        Ok(())
+    }
+}
+
+pub struct UndefinedPhony {
+    skip_with_includes: bool,
+}
+
+impl UndefinedPhony {
+    pub fn new() -> Self {
+        Self {
+            skip_with_includes: true,
+        }
+    }
+}
+
+impl Default for UndefinedPhony {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for UndefinedPhony {
+    fn id(&self) -> &'static str {
+        "MK210"
+    }
+
+    fn name(&self) -> &'static str {
+        ".PHONY target is never defined"
+    }
+
+    fn description(&self) -> &'static str {
+        "A name declared in `.PHONY` without a matching rule usually indicates a typo. \
+         Because the rule may live in an included file, the check is skipped for files \
+         with `include` directives unless the `skip_with_includes` option is false or the \
+         included files are read with `--follow-includes`."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        if self.skip_with_includes && !makefile.includes.is_empty() && makefile.included.is_empty()
+        {
+            return diagnostics;
+        }
+
+        // Whether the previous line is a `.PHONY:` declaration continued with `\`
+        let mut continued = false;
+        for (idx, line) in content.lines().enumerate() {
+            let names = if continued {
+                Some(line)
+            } else {
+                line.trim_start().strip_prefix(".PHONY:")
+            };
+            let Some(names) = names else {
+                continue;
+            };
+            continued = line.ends_with('\\');
+            let names = names.split('#').next().unwrap_or(names).trim_end();
+            let names = names.strip_suffix('\\').unwrap_or(names);
+
+            for name in names.split_whitespace() {
+                let defined = makefile
+                    .files()
+                    .flat_map(|file| &file.rules)
+                    .any(|rule| rule.targets.iter().any(|target| target == name));
+
+                if !defined {
+                    let column = line.find(name).map_or(1, |pos| pos + 1);
+                    diagnostics.push(Diagnostic::new(
+                        self.id(),
+                        Severity::Warning,
+                        format!("Phony target '{name}' is never defined"),
+                        idx + 1,
+                        column,
+                    ));
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "skip_with_includes",
+            OptionKind::Boolean,
+            self.skip_with_includes,
+            "Skip files with include directives, whose targets may be defined elsewhere",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(skip) = options.get("skip_with_includes") {
+            let Some(skip) = skip.as_bool() else {
+                bail!("option 'skip_with_includes' must be a boolean");
+            };
+            self.skip_with_includes = skip;
+        }
+        Ok(())
+    }
+}
+
+pub struct BacktickSubstitution;
+
+impl Rule for BacktickSubstitution {
+    fn id(&self) -> &'static str {
+        "MK211"
+    }
+
+    fn name(&self) -> &'static str {
+        "Prefer $(...) over backticks"
+    }
+
+    fn description(&self) -> &'static str {
+        "Backtick command substitution is hard to nest and quote. Use POSIX `$(...)` \
+         instead, written as `$$(...)` in recipes so that Make passes it to the shell."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+
+        for rule in &makefile.rules {
+            for recipe in &rule.recipes {
+                let Some((open, close)) = find_backtick_pair(&recipe.command) else {
+                    continue;
+                };
+                let offset = command_offset(recipe);
+                let open_column = offset + open + 1;
+                let close_column = offset + close + 1;
+                let diagnostic = Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    "Use $$(...) instead of backticks for command substitution",
+                    recipe.line,
+                    open_column,
+                );
+
+                // Positions past the first line of a continued command don't map back to
+                // the source, so those are reported without a fix
+                let first_line = lines[recipe.line - 1];
+                if first_line.get(offset + open..=offset + close)
+                    != recipe.command.get(open..=close)
+                {
+                    diagnostics.push(diagnostic);
+                    continue;
+                }
+
+                let fix = Fix::new("Replace backticks with $$(...)")
+                    .add_edit(Edit::new(
+                        recipe.line,
+                        open_column,
+                        recipe.line,
+                        open_column + 1,
+                        "$$(",
+                    ))
+                    .add_edit(Edit::new(
+                        recipe.line,
+                        close_column,
+                        recipe.line,
+                        close_column + 1,
+                        ")",
+                    ));
+
+                diagnostics.push(diagnostic.with_fix(fix));
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// Finds the first pair of backticks outside single quotes.
+fn find_backtick_pair(command: &str) -> Option<(usize, usize)> {
+    let mut in_single_quotes = false;
+    let mut open = None;
+
+    for (idx, c) in command.char_indices() {
+        match c {
+            '\'' if open.is_none() => in_single_quotes = !in_single_quotes,
+            '`' if !in_single_quotes => match open {
+                Some(start) => return Some((start, idx)),
+                None => open = Some(idx),
+            },
+            _ => {}
+        }
+    }
+
+    None
+}
+
+pub struct ImplicitDefaultGoal;
+
+impl Rule for ImplicitDefaultGoal {
+    fn id(&self) -> &'static str {
+        "MK212"
+    }
+
+    fn name(&self) -> &'static str {
+        "Implicit default goal"
+    }
+
+    fn description(&self) -> &'static str {
+        "Without `.DEFAULT_GOAL`, running `make` builds the first target in the file, which \
+         silently changes when rules are reordered. Start with an `all` target or set \
+         `.DEFAULT_GOAL` explicitly."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        if makefile.default_goal.is_some() {
+            return Vec::new();
+        }
+
+        let first_target = makefile
+            .rules
+            .iter()
+            .flat_map(|rule| &rule.targets)
+            .find(|target| !target.starts_with('.') && !target.contains('%'));
+
+        match first_target {
+            Some(target) if target != "all" => vec![Diagnostic::new(
+                self.id(),
+                Severity::Warning,
+                format!(
+                    "Default goal is implicitly '{target}'; add an 'all' target first or set \
+                     .DEFAULT_GOAL"
+                ),
+                1,
+                1,
+            )],
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub struct ShellWithoutFlags;
+
+impl Rule for ShellWithoutFlags {
+    fn id(&self) -> &'static str {
+        "MK213"
+    }
+
+    fn name(&self) -> &'static str {
+        "SHELL set without .SHELLFLAGS"
+    }
+
+    fn description(&self) -> &'static str {
+        "Switching `SHELL` to bash or another shell keeps the default `-c` flags, so failures \
+         inside pipelines go unnoticed. Set `.SHELLFLAGS` alongside it, for example \
+         `.SHELLFLAGS := -eu -o pipefail -c`."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let Some(shell) = makefile.variables.get("SHELL") else {
+            return Vec::new();
+        };
+        if matches!(shell.value.trim(), "/bin/sh" | "sh") {
+            return Vec::new();
+        }
+
+        let has_shellflags = content.lines().any(|line| {
+            line.trim_start()
+                .strip_prefix(".SHELLFLAGS")
+                .is_some_and(|rest| rest.trim_start().contains('='))
+        });
+        if has_shellflags {
+            return Vec::new();
+        }
+
+        vec![Diagnostic::new(
+            self.id(),
+            Severity::Warning,
+            format!("SHELL is set to '{}' without .SHELLFLAGS", shell.value),
+            shell.line,
+            shell.column,
+        )]
+    }
+}
+
+const DEFAULT_TOOL_VARIABLES: &[&str] = &[
+    "CC", "CXX", "CPP", "FC", "AR", "AS", "LD", "RANLIB", "CFLAGS", "CXXFLAGS", "CPPFLAGS",
+    "FFLAGS", "ASFLAGS", "ARFLAGS", "LDFLAGS", "LDLIBS",
+];
+
+/// Variables that Make's built-in rules give a default value, which `?=` never replaces.
+const MAKE_DEFAULT_VARIABLES: &[&str] = &[
+    "AR", "AS", "CC", "CO", "CPP", "CTANGLE", "CWEAVE", "CXX", "FC", "GET", "LD", "LEX", "LINT",
+    "M2C", "MAKEINFO", "PC", "RM", "TANGLE", "TEX", "TEXI2DVI", "WEAVE", "YACC", "ARFLAGS",
+];
+
+pub struct ToolVariableOverride {
+    variables: Vec<String>,
+}
+
+impl ToolVariableOverride {
+    pub fn new() -> Self {
+        Self {
+            variables: DEFAULT_TOOL_VARIABLES
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Default for ToolVariableOverride {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for ToolVariableOverride {
+    fn id(&self) -> &'static str {
+        "MK214"
+    }
+
+    fn name(&self) -> &'static str {
+        "Tool variable not assigned with ?="
+    }
+
+    fn description(&self) -> &'static str {
+        "Assigning tool and flag variables such as `CC` or `CFLAGS` with `=` or `:=` discards \
+         values users pass through the environment. `?=` only sets a default. Variables such \
+         as `CC` already have a default from Make, so `?=` leaves them unchanged; assign \
+         those inside `ifeq ($(origin CC),default)` instead. The checked names are set with \
+         the `variables` option."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+        let contexts = conditional_contexts(content);
+
+        for variable in &makefile.assignments {
+            let overriding = matches!(
+                variable.operator,
+                AssignmentOperator::Recursive
+                    | AssignmentOperator::Simple
+                    | AssignmentOperator::PosixSimple
+            );
+            if !overriding || !self.variables.contains(&variable.name) {
+                continue;
+            }
+
+            let operator = variable.operator.as_str();
+            if MAKE_DEFAULT_VARIABLES.contains(&variable.name.as_str()) {
+                // Assignments that only replace Make's default are what the rule asks for
+                let origin = format!("(origin {})", variable.name);
+                let guarded = contexts.get(variable.line - 1).is_some_and(|context| {
+                    context.iter().any(|&(line, _)| {
+                        lines
+                            .get(line - 1)
+                            .is_some_and(|line| line.contains(&origin))
+                    })
+                });
+                if guarded {
+                    continue;
+                }
+                diagnostics.push(Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "'{name}' is assigned with '{operator}', overriding the environment; \
+                         Make already defines '{name}', so assign it inside \
+                         'ifeq ($(origin {name}),default)'",
+                        name = variable.name
+                    ),
+                    variable.line,
+                    variable.operator_column,
+                ));
+                continue;
+            }
+
+            // Environment values now take precedence, and `:=` becomes recursively expanded
+            let fix = Fix::new(format!("Replace '{operator}' with '?='"))
+                .as_unsafe()
+                .add_edit(Edit::new(
+                    variable.line,
+                    variable.operator_column,
+                    variable.line,
+                    variable.operator_column + operator.len(),
+                    "?=",
+                ));
+
+            diagnostics.push(
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "'{}' is assigned with '{operator}', overriding the environment; use '?='",
+                        variable.name
+                    ),
+                    variable.line,
+                    variable.operator_column,
+                )
+                .with_fix(fix),
+            );
+        }
+
+        diagnostics.sort_by_key(|d| d.line);
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "variables",
+            OptionKind::StringArray,
+            self.variables.clone(),
+            "Tool variables that should be assigned with ?=",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(variables) = options.get("variables") {
+            let Some(variables) = variables.as_array().and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect()
+            }) else {
+                bail!("option 'variables' must be an array of strings");
+            };
+            self.variables = variables;
+        }
+        Ok(())
+    }
+}
+
+/// Bash-only constructs, each with a capture group around the construct itself.
+static BASHISMS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    [
+        (r"(?:^|[\s;&|(])(\[\[)\s", "[[ ... ]] test"),
+        (r"(<<<)", "here-string (<<<)"),
+        // Commands at the start of a line follow the recipe tab and any `@`, `-` or `+`
+        (r"(?:^[\t @+-]*|[;&|(]\s*)(source)\s", "'source' builtin"),
+        (
+            r"(?:^[\t @+-]*|[;&|(]\s*)(function)\s+\w+",
+            "'function' keyword",
+        ),
+        (r"(?:^|[\s;&|(])(\w+=\()", "array assignment"),
+        (r"(\$\$\{\w+\[)", "array expansion"),
+    ]
+    .into_iter()
+    .map(|(pattern, construct)| (Regex::new(pattern).unwrap(), construct))
+    .collect()
+});
+
+pub struct Bashism;
+
+impl Rule for Bashism {
+    fn id(&self) -> &'static str {
+        "MK215"
+    }
+
+    fn name(&self) -> &'static str {
+        "Bash-specific syntax in a /bin/sh recipe"
+    }
+
+    fn description(&self) -> &'static str {
+        "Recipes run with `/bin/sh` unless `SHELL` is set, which on many systems is a POSIX \
+         shell such as dash. Constructs like `[[ ... ]]`, `<<<`, `source` and arrays then \
+         fail. Use POSIX equivalents or set `SHELL := /bin/bash`."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let posix_shell = makefile.variables.get("SHELL").is_none_or(|shell| {
+            let program = shell.value.split_whitespace().next().unwrap_or_default();
+            matches!(program.rsplit('/').next(), Some("sh" | "dash"))
+        });
+        if !posix_shell {
+            return Vec::new();
+        }
+
+        let mut diagnostics = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+
+        for rule in &makefile.rules {
+            for recipe in &rule.recipes {
+                for line_num in recipe.line..=recipe.end_line {
+                    let Some(line) = lines.get(line_num - 1) else {
+                        continue;
+                    };
+
+                    for (pattern, construct) in BASHISMS.iter() {
+                        let Some(found) = pattern.captures(line).and_then(|c| c.get(1)) else {
+                            continue;
+                        };
+                        diagnostics.push(
+                            Diagnostic::new(
+                                self.id(),
+                                Severity::Warning,
+                                format!("Bash {construct} used, but recipes run with /bin/sh"),
+                                line_num,
+                                found.start() + 1,
+                            )
+                            .with_span(line_num, found.end() + 1),
+                        );
+                    }
+                }
+            }
+        }
+
+        diagnostics.sort_by_key(|d| (d.line, d.column));
+        diagnostics
+    }
+}
+
+pub struct UndefinedPrerequisite {
+    check_fs: bool,
+}
+
+impl UndefinedPrerequisite {
+    pub fn new() -> Self {
+        Self { check_fs: false }
+    }
+
+    /// Whether `name` exists on disk, relative to the Makefile or one of its search paths.
+    fn exists_on_disk(&self, makefile: &Makefile, name: &str) -> bool {
+        let base = makefile
+            .path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+
+        base.join(name).exists()
+            || makefile
+                .vpaths
+                .iter()
+                .flat_map(|vpath| &vpath.directories)
+                .any(|dir| base.join(dir).join(name).exists())
+    }
+}
+
+impl Default for UndefinedPrerequisite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for UndefinedPrerequisite {
+    fn id(&self) -> &'static str {
+        "MK216"
+    }
+
+    fn name(&self) -> &'static str {
+        "Prerequisite is not defined as a target"
+    }
+
+    fn description(&self) -> &'static str {
+        "A prerequisite that no rule, pattern rule or .PHONY declaration provides may be a \
+         typo. Without file system checks (the `check_fs` option or the `--check-fs` flag), \
+         prerequisites that look like file paths are assumed to exist."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let targets: Vec<&str> = makefile
+            .files()
+            .flat_map(|file| {
+                file.rules
+                    .iter()
+                    .flat_map(|rule| &rule.targets)
+                    .chain(&file.phonies)
+            })
+            .map(String::as_str)
+            .collect();
+        let provided = |name: &str| {
+            targets.iter().any(|target| match target.split_once('%') {
+                Some((prefix, suffix)) => {
+                    name.len() > prefix.len() + suffix.len()
+                        && name.starts_with(prefix)
+                        && name.ends_with(suffix)
+                }
+                None => *target == name,
+            })
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            for prerequisite in rule
+                .prerequisites
+                .iter()
+                .chain(&rule.order_only_prerequisites)
+            {
+                // Names built from variables or patterns can't be resolved statically
+                if prerequisite.contains(['$', '%']) || provided(prerequisite) {
+                    continue;
+                }
+                let looks_like_file = prerequisite.contains(['.', '/']);
+                if self.check_fs {
+                    if self.exists_on_disk(makefile, prerequisite) {
+                        continue;
+                    }
+                } else if looks_like_file {
+                    continue;
+                }
+
+                let column = lines
+                    .get(rule.line - 1)
+                    .and_then(|line| {
+                        let colon = line.find(':')?;
+                        line[colon..]
+                            .split_whitespace()
+                            .find(|word| *word == prerequisite)
+                            .map(|word| word.as_ptr() as usize - line.as_ptr() as usize + 1)
+                    })
+                    .unwrap_or(rule.column);
+
+                diagnostics.push(
+                    Diagnostic::new(
+                        self.id(),
+                        Severity::Info,
+                        format!(
+                            "Prerequisite '{prerequisite}' is not defined as a target; it may be a typo"
+                        ),
+                        rule.line,
+                        column,
+                    )
+                    .with_span(rule.line, column + prerequisite.len()),
+                );
+            }
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "check_fs",
+            OptionKind::Boolean,
+            self.check_fs,
+            "Accept prerequisites that exist as files next to the Makefile",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(check_fs) = options.get("check_fs") {
+            match check_fs.as_bool() {
+                Some(check_fs) => self.check_fs = check_fs,
+                None => bail!("option 'check_fs' must be a boolean"),
+            }
+        }
+        Ok(())
+    }
+}
+
+static CHMOD_MODE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[\s;&|(])chmod\s+(?:-\S+\s+)*(\S+)").unwrap());
+
+/// Whether a `chmod` mode grants write permission to other users, e.g. `777`, `o+w` or
+/// `a=rwx`.
+fn is_world_writable(mode: &str) -> bool {
+    if mode.chars().all(|c| c.is_digit(8)) {
+        return mode
+            .chars()
+            .last()
+            .and_then(|others| others.to_digit(8))
+            .is_some_and(|others| others & 2 != 0);
+    }
+
+    mode.split(',').any(|clause| {
+        let Some(op) = clause.find(['+', '=']) else {
+            return false;
+        };
+        let (who, perms) = clause.split_at(op);
+        who.contains(['o', 'a']) && perms.contains('w')
+    })
+}
+
+pub struct PermissiveChmod;
+
+impl Rule for PermissiveChmod {
+    fn id(&self) -> &'static str {
+        "MK217"
+    }
+
+    fn name(&self) -> &'static str {
+        "Overly permissive chmod"
+    }
+
+    fn description(&self) -> &'static str {
+        "`chmod 777` and similar modes make files writable by every user on the system. \
+         Grant only the permissions that are needed, e.g. `755` for executables and `644` \
+         for other files."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            for recipe in &rule.recipes {
+                for captures in CHMOD_MODE.captures_iter(&recipe.command) {
+                    let mode = &captures[1];
+                    if !is_world_writable(mode) {
+                        continue;
+                    }
+
+                    diagnostics.push(Diagnostic::new(
+                        self.id(),
+                        Severity::Warning,
+                        format!(
+                            "chmod mode '{mode}' makes files world-writable; use a tighter mode \
+                             such as 755 or 644"
+                        ),
+                        recipe.line,
+                        recipe.column,
+                    ));
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// Variables usually inherited from the environment, which are exported without a definition.
+const ENVIRONMENT_VARIABLES: &[&str] = &[
+    "PATH", "HOME", "USER", "SHELL", "LANG", "LC_ALL", "TERM", "TMPDIR", "PWD",
+];
+
+pub struct ExportUndefined;
+
+impl Rule for ExportUndefined {
+    fn id(&self) -> &'static str {
+        "MK218"
+    }
+
+    fn name(&self) -> &'static str {
+        "Export of an undefined variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "`export NAME` for a variable that is never assigned in the Makefile exports an empty \
+         value unless it happens to be set in the environment, which is usually a mistake. \
+         Common environment variables such as PATH and HOME are exempt."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        makefile
+            .exports
+            .iter()
+            .filter(|export| {
+                !makefile
+                    .files()
+                    .any(|file| file.variables.contains_key(&export.name))
+                    && !export.name.contains('$')
+                    && !ENVIRONMENT_VARIABLES.contains(&export.name.as_str())
+            })
+            .map(|export| {
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "Exported variable '{}' is never assigned in this Makefile",
+                        export.name
+                    ),
+                    export.line,
+                    export.column,
+                )
+                .with_span(export.line, export.column + export.name.len())
+            })
+            .collect()
+    }
+}
+
+/// Variables that Make itself reads, or that are consumed by its built-in rules.
+const SPECIAL_VARIABLES: &[&str] = &[
+    "SHELL",
+    ".SHELLFLAGS",
+    "MAKEFLAGS",
+    "GNUMAKEFLAGS",
+    "MAKEFILES",
+    "MAKE",
+    "VPATH",
+    "SUFFIXES",
+    ".DEFAULT_GOAL",
+    ".RECIPEPREFIX",
+    ".EXTRA_PREREQS",
+    "MAKECMDGOALS",
+    "TARGET_ARCH",
+    "OUTPUT_OPTION",
+    "LEX",
+    "YACC",
+    "LFLAGS",
+    "YFLAGS",
+    "RM",
+];
+
+pub struct UnusedVariable;
+
+impl Rule for UnusedVariable {
+    fn id(&self) -> &'static str {
+        "MK219"
+    }
+
+    fn name(&self) -> &'static str {
+        "Variable is never used"
+    }
+
+    fn description(&self) -> &'static str {
+        "A variable that is assigned but never referenced in a recipe, prerequisite, another \
+         variable or a conditional is likely left over from an earlier version. Exported \
+         variables and variables read by Make itself are exempt. Variables may still be used \
+         by included or including Makefiles, and the rule is skipped when the file computes \
+         variable names such as `$($(NAME)_FLAGS)`."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let referenced: Vec<&str> = makefile
+            .files()
+            .flat_map(|file| &file.references)
+            .filter(|reference| reference.kind == ReferenceKind::Variable)
+            .map(|reference| reference.name.as_str())
+            .collect();
+        // Any variable may be read through a computed name
+        if referenced.iter().any(|name| name.contains('$')) {
+            return Vec::new();
+        }
+
+        let mut unused: Vec<_> = makefile
+            .variables
+            .values()
+            .filter(|variable| {
+                let name = variable.name.as_str();
+                !referenced.contains(&name)
+                    && !SPECIAL_VARIABLES.contains(&name)
+                    && !DEFAULT_TOOL_VARIABLES.contains(&name)
+                    && !makefile
+                        .files()
+                        .flat_map(|file| &file.exports)
+                        .any(|export| export.name == name)
+            })
+            .collect();
+        unused.sort_by_key(|variable| variable.line);
+
+        unused
+            .into_iter()
+            .map(|variable| {
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Info,
+                    format!("Variable '{}' is assigned but never used", variable.name),
+                    variable.line,
+                    variable.column,
+                )
+                .with_span(variable.line, variable.column + variable.name.len())
+            })
+            .collect()
+    }
+}
+
+const DEFAULT_EXPENSIVE_COMMANDS: &[&str] = &["git", "curl", "wget", "find", "docker"];
+
+static SHELL_CALL: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$[({]shell\s+([^\s()}]+)").unwrap());
+
+pub struct EagerShell {
+    commands: Vec<String>,
+}
+
+impl EagerShell {
+    pub fn new() -> Self {
+        Self {
+            commands: DEFAULT_EXPENSIVE_COMMANDS
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Default for EagerShell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for EagerShell {
+    fn id(&self) -> &'static str {
+        "MK220"
+    }
+
+    fn name(&self) -> &'static str {
+        "Expensive $(shell) call at parse time"
+    }
+
+    fn description(&self) -> &'static str {
+        "A `$(shell ...)` in a `:=` assignment runs while the Makefile is read, on every \
+         invocation and for every target, even ones that never use the variable. Move slow \
+         commands into the recipes that need them, or guard the assignment. The `commands` \
+         option lists the commands considered expensive (git, curl, wget, find and docker \
+         by default)."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let variables = makefile.assignments.iter().filter(|variable| {
+            matches!(
+                variable.operator,
+                AssignmentOperator::Simple | AssignmentOperator::PosixSimple
+            )
+        });
+
+        let mut diagnostics = Vec::new();
+        for variable in variables {
+            let Some(command) = SHELL_CALL
+                .captures_iter(&variable.value)
+                .map(|captures| captures.get(1).unwrap().as_str())
+                .map(|command| command.rsplit('/').next().unwrap_or(command))
+                .find(|command| self.commands.iter().any(|c| c == command))
+            else {
+                continue;
+            };
+
+            let column = makefile
+                .references
+                .iter()
+                .find(|r| r.line == variable.line && r.name == "shell")
+                .map_or(variable.column, |r| r.column);
+            diagnostics.push(Diagnostic::new(
+                self.id(),
+                Severity::Info,
+                format!(
+                    "'{}' runs `{command}` every time the Makefile is read; consider moving it \
+                     into a recipe",
+                    variable.name
+                ),
+                variable.line,
+                column,
+            ));
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "commands",
+            OptionKind::StringArray,
+            self.commands.clone(),
+            "Commands considered expensive in an immediate $(shell) call",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(commands) = options.get("commands") {
+            let Some(commands) = commands.as_array().and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect()
+            }) else {
+                bail!("option 'commands' must be an array of strings");
+            };
+            self.commands = commands;
+        }
+        Ok(())
+    }
+}
+
+/// Commands whose failure is routinely ignored, e.g. removing files that may not exist.
+const DEFAULT_IGNORABLE_COMMANDS: &[&str] = &[
+    "rm", "$(RM)", "rmdir", "mkdir", "ln", "kill", "pkill", "killall", "make", "$(MAKE)", "diff",
+    "cmp",
+];
+
+pub struct IgnoredError {
+    allowed: Vec<String>,
+}
+
+impl IgnoredError {
+    pub fn new() -> Self {
+        Self {
+            allowed: DEFAULT_IGNORABLE_COMMANDS
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Default for IgnoredError {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for IgnoredError {
+    fn id(&self) -> &'static str {
+        "MK221"
+    }
+
+    fn name(&self) -> &'static str {
+        "Ignored error may mask a failure"
+    }
+
+    fn description(&self) -> &'static str {
+        "The `-` prefix makes Make carry on when a command fails. That is idiomatic for \
+         commands like `rm` in a clean target, but for compilers, tests or installs it hides \
+         real failures. The `allowed` option lists the commands for which `-` is accepted."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for recipe in makefile.rules.iter().flat_map(|rule| &rule.recipes) {
+            if !recipe.ignore_error {
+                continue;
+            }
+            let Some(command) = recipe.command.split_whitespace().next() else {
+                continue;
+            };
+            let name = command.rsplit('/').next().unwrap_or(command);
+            if self.allowed.iter().any(|allowed| allowed == name) {
+                continue;
+            }
+
+            // The prefix may also contain `@` and `+`, in any order
+            let dash_column = recipe.indentation.len() + recipe.prefix.find('-').unwrap_or(0) + 1;
+            diagnostics.push(
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Info,
+                    format!("The - prefix on '{name}' may mask real failures"),
+                    recipe.line,
+                    dash_column,
+                )
+                .with_fix(Fix::new("Remove - prefix").as_unsafe().add_edit(
+                    Edit::new(
+                        recipe.line,
+                        dash_column,
+                        recipe.line,
+                        dash_column + 1,
+                        String::new(),
+                    ),
+                )),
+            );
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "allowed",
+            OptionKind::StringArray,
+            self.allowed.clone(),
+            "Commands whose failures may be ignored with -",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(allowed) = options.get("allowed") {
+            let Some(allowed) = allowed.as_array().and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect()
+            }) else {
+                bail!("option 'allowed' must be an array of strings");
+            };
+            self.allowed = allowed;
+        }
+        Ok(())
+    }
+}
+
+pub struct RequiredInclude;
+
+impl Rule for RequiredInclude {
+    fn id(&self) -> &'static str {
+        "MK222"
+    }
+
+    fn name(&self) -> &'static str {
+        "Required include of a relative path"
+    }
+
+    fn description(&self) -> &'static str {
+        "A plain `include` aborts make when the file is missing. Paths built from variables \
+         or wildcards, such as generated dependency files, often don't exist on a clean \
+         checkout; use `-include` for files that are allowed to be absent."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for include in makefile.includes.iter().filter(|include| !include.optional) {
+            let Some(path) = include.paths.iter().find(|path| !path.starts_with('/')) else {
+                continue;
+            };
+
+            let (severity, message) = if path.contains('$') || path.contains(['*', '?', '[']) {
+                (
+                    Severity::Warning,
+                    format!("Include of '{path}' may match no file and abort make"),
+                )
+            } else {
+                (
+                    Severity::Info,
+                    format!("Include of '{path}' aborts make if the file is missing"),
+                )
+            };
+
+            diagnostics.push(
+                Diagnostic::new(self.id(), severity, message, include.line, include.column)
+                    .with_fix(Fix::new("Use -include").as_unsafe().add_edit(Edit::new(
+                        include.line,
+                        include.column,
+                        include.line,
+                        include.column,
+                        "-".to_string(),
+                    ))),
+            );
+        }
+
+        diagnostics
+    }
+}
+
+static SUDO: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|[\s;&|(])sudo(?:\s|$)").unwrap());
+
+pub struct Sudo;
+
+impl Rule for Sudo {
+    fn id(&self) -> &'static str {
+        "MK223"
+    }
+
+    fn name(&self) -> &'static str {
+        "Recipe uses sudo"
+    }
+
+    fn description(&self) -> &'static str {
+        "Building shouldn't require root: `sudo` prompts for a password or fails outright in \
+         CI and sandboxed builds. Keep privileged steps in an install target the user runs \
+         deliberately, or document that it must be run as root."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            for recipe in &rule.recipes {
+                // Ignore commented-out commands and shell comments after a command
+                if recipe.command.trim_start().starts_with('#') {
+                    continue;
+                }
+                let command = recipe.command.split(" #").next().unwrap_or_default();
+                if !SUDO.is_match(command) {
+                    continue;
+                }
+
+                diagnostics.push(Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "Recipe for '{}' runs sudo; move the privileged step to an install \
+                         target or document that it needs root",
+                        rule.targets.join(" ")
+                    ),
+                    recipe.line,
+                    recipe.column,
+                ));
+            }
+        }
+
+        diagnostics
+    }
+}
+
+pub struct MissingOutputDirectory;
+
+impl Rule for MissingOutputDirectory {
+    fn id(&self) -> &'static str {
+        "MK224"
+    }
+
+    fn name(&self) -> &'static str {
+        "Target in a directory the recipe doesn't create"
+    }
+
+    fn description(&self) -> &'static str {
+        "A recipe writing `build/app` fails when `build/` doesn't exist yet, as in a fresh \
+         checkout. Create the directory in the recipe with `mkdir -p $(@D)`, or add it as an \
+         order-only prerequisite (`build/app: main.c | build`) with its own rule. Targets \
+         next to one of their prerequisites are skipped, since that directory already exists."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            if rule.recipes.is_empty()
+                || rule
+                    .recipes
+                    .iter()
+                    .any(|recipe| recipe.command.contains("mkdir"))
+            {
+                continue;
+            }
+            let prerequisites: Vec<&str> = rule
+                .prerequisites
+                .iter()
+                .chain(&rule.order_only_prerequisites)
+                .map(|prerequisite| prerequisite.trim_end_matches('/'))
+                .collect();
+
+            for target in &rule.targets {
+                let Some((directory, _)) = target.rsplit_once('/') else {
+                    continue;
+                };
+                if directory.is_empty() || makefile.is_phony(target) {
+                    continue;
+                }
+                // The directory, one of its parents, or a file in it is a prerequisite
+                let covered = prerequisites.iter().any(|prerequisite| {
+                    directory == *prerequisite
+                        || directory.starts_with(&format!("{prerequisite}/"))
+                        || prerequisite.starts_with(&format!("{directory}/"))
+                });
+                if covered {
+                    continue;
+                }
+
+                diagnostics.push(Diagnostic::new(
+                    self.id(),
+                    Severity::Info,
+                    format!(
+                        "Recipe for '{target}' doesn't create '{directory}'; add 'mkdir -p $(@D)' \
+                         or an order-only prerequisite '| {directory}'"
+                    ),
+                    rule.line,
+                    rule.column,
+                ));
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// GNU Make functions that other makes lack, with what to use instead
+const GNU_FUNCTIONS: &[(&str, &str)] = &[
+    (
+        "abspath",
+        "build the path from the working directory in the recipe",
+    ),
+    ("realpath", "resolve the path with `realpath` in the recipe"),
+    ("file", "write the file with `printf` in a recipe"),
+    ("guile", "move the logic into a script called from a recipe"),
+];
+
+pub struct GnuFunction {
+    portable: bool,
+}
+
+impl GnuFunction {
+    pub fn new() -> Self {
+        Self { portable: false }
+    }
+}
+
+impl Default for GnuFunction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for GnuFunction {
+    fn id(&self) -> &'static str {
+        "MK225"
+    }
+
+    fn name(&self) -> &'static str {
+        "GNU-specific function in a portable Makefile"
+    }
+
+    fn description(&self) -> &'static str {
+        "Functions like `$(abspath)`, `$(realpath)`, `$(file)` and `$(guile)` only exist in \
+         GNU Make, so BSD make and other POSIX makes can't read a Makefile using them. The \
+         rule checks Makefiles declaring `.POSIX:`, or every Makefile when the `portable` \
+         option is set. This rule is opt-in: enable it in the config file to use it."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn opt_in(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        if !self.portable && !makefile.special_targets.contains_key(".POSIX") {
+            return Vec::new();
+        }
+
+        let mut diagnostics = Vec::new();
+        for reference in &makefile.references {
+            if reference.kind != ReferenceKind::Function {
+                continue;
+            }
+            let Some((name, alternative)) = GNU_FUNCTIONS
+                .iter()
+                .find(|(name, _)| *name == reference.name)
+            else {
+                continue;
+            };
+
+            diagnostics.push(Diagnostic::new(
+                self.id(),
+                Severity::Warning,
+                format!("'$({name})' is specific to GNU Make; {alternative}"),
+                reference.line,
+                reference.column,
+            ));
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "portable",
+            OptionKind::Boolean,
+            self.portable,
+            "Check every Makefile, not only those declaring .POSIX",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(portable) = options.get("portable") {
+            let Some(portable) = portable.as_bool() else {
+                bail!("option 'portable' must be a boolean");
+            };
+            self.portable = portable;
+        }
+        Ok(())
+    }
+}
+
+pub struct MisplacedDependency;
+
+impl Rule for MisplacedDependency {
+    fn id(&self) -> &'static str {
+        "MK226"
+    }
+
+    fn name(&self) -> &'static str {
+        "Prerequisites added after the target's recipe"
+    }
+
+    fn description(&self) -> &'static str {
+        "A second `target: dep` line after the target's recipe starts a new rule rather than \
+         continuing the first one. Without a recipe it only adds prerequisites, which is easy \
+         to miss when reading the first rule; with a recipe, Make discards the first recipe \
+         with an \"overriding recipe\" warning. List every prerequisite on the first rule. \
+         Double-colon, pattern and special-target rules are exempt, as are rules in different \
+         branches of a conditional."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let contexts = conditional_contexts(content);
+        let context = |line: usize| contexts.get(line - 1).cloned().unwrap_or_default();
+        // Lines in different branches of the same conditional are never both read
+        let exclusive = |a: usize, b: usize| {
+            let (a, b) = (context(a), context(b));
+            a.iter()
+                .any(|(start, branch)| b.iter().any(|(s, other)| s == start && other != branch))
+        };
+        let mut diagnostics = Vec::new();
+        // Rules with a recipe for each target, in order
+        let mut with_recipe: HashMap<&str, Vec<usize>> = HashMap::new();
+
+        for rule in &makefile.rules {
+            if rule.double_colon {
+                continue;
+            }
+            let targets = rule
+                .targets
+                .iter()
+                .filter(|target| !target.starts_with('.') && !target.contains('%'));
+
+            if !rule.prerequisites.is_empty() {
+                let earlier = targets.clone().find_map(|target| {
+                    let lines = with_recipe.get(target.as_str())?;
+                    let line = lines.iter().find(|&&line| !exclusive(line, rule.line))?;
+                    Some((target, *line))
+                });
+                if let Some((target, line)) = earlier {
+                    let (severity, message) = if rule.recipes.is_empty() {
+                        (
+                            Severity::Info,
+                            format!(
+                                "Prerequisites of '{target}' are added after its recipe on \
+                                 line {line}; list them on that rule"
+                            ),
+                        )
+                    } else {
+                        (
+                            Severity::Warning,
+                            format!(
+                                "This rule overrides the recipe of '{target}' on line {line}; \
+                                 merge its prerequisites into that rule"
+                            ),
+                        )
+                    };
+                    diagnostics.push(Diagnostic::new(
+                        self.id(),
+                        severity,
+                        message,
+                        rule.line,
+                        rule.column,
+                    ));
+                }
+            }
+
+            if !rule.recipes.is_empty() {
+                for target in targets {
+                    with_recipe.entry(target).or_default().push(rule.line);
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
+
+pub struct IncludeCycle {
+    follow_includes: bool,
+}
+
+impl IncludeCycle {
+    pub fn new() -> Self {
+        Self {
+            follow_includes: false,
+        }
+    }
+}
+
+impl Default for IncludeCycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for IncludeCycle {
+    fn id(&self) -> &'static str {
+        "MK227"
+    }
+
+    fn name(&self) -> &'static str {
+        "Include cycle"
+    }
+
+    fn description(&self) -> &'static str {
+        "A Makefile that includes itself, directly or through other included files, makes \
+         Make read the same files over and over until it gives up. Included paths are \
+         resolved relative to the checked Makefile's directory, as Make resolves them from \
+         the directory it runs in; paths with variables or wildcards are not \
+         followed. The rule only reads other files with the `follow_includes` option or the \
+         `--follow-includes` flag. This rule is opt-in: enable it in the config file to use it."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn opt_in(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        if !self.follow_includes {
+            return Vec::new();
+        }
+        let Some(path) = &makefile.path else {
+            return Vec::new();
+        };
+        let Some((include, chain)) = includes::find_cycle(path, makefile) else {
+            return Vec::new();
+        };
+
+        // Name the files relative to this one's directory where possible
+        let dir = chain[0].parent().unwrap_or(Path::new(""));
+        let files: Vec<String> = chain
+            .iter()
+            .map(|file| file.strip_prefix(dir).unwrap_or(file).display().to_string())
+            .collect();
+        vec![Diagnostic::new(
+            self.id(),
+            Severity::Error,
+            format!("Include cycle: {}", files.join(" -> ")),
+            include.line,
+            include.column,
+        )]
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "follow_includes",
+            OptionKind::Boolean,
+            self.follow_includes,
+            "Read included files to look for cycles",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(follow_includes) = options.get("follow_includes") {
+            let Some(follow_includes) = follow_includes.as_bool() else {
+                bail!("option 'follow_includes' must be a boolean");
+            };
+            self.follow_includes = follow_includes;
+        }
+        Ok(())
+    }
+}
+
+pub struct MissingIncludeFile;
+
+impl Rule for MissingIncludeFile {
+    fn id(&self) -> &'static str {
+        "MK228"
+    }
+
+    fn name(&self) -> &'static str {
+        "Included file not found"
+    }
+
+    fn description(&self) -> &'static str {
+        "Make stops with \"No such file or directory\" when a file named by `include` doesn't \
+         exist and no rule can make it. Use `-include` if the file is optional. Files are \
+         only looked up when includes are followed with `--follow-includes`, relative to the \
+         directory of the checked Makefile, where Make runs; files that a rule builds are \
+         exempt. Missing files named by included files are reported at the directive \
+         including them."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::BestPractices
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        makefile
+            .missing_includes
+            .iter()
+            .filter(|missing| {
+                !makefile
+                    .files()
+                    .flat_map(|file| &file.rules)
+                    .any(|rule| rule.targets.contains(&missing.path))
+            })
+            .map(|missing| {
+                let message = match &missing.included_from {
+                    Some(from) => format!(
+                        "File '{}' included from '{from}' does not exist",
+                        missing.path
+                    ),
+                    None => format!("Included file '{}' does not exist", missing.path),
+                };
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Error,
+                    message,
+                    missing.line,
+                    missing.column,
+                )
+            })
+            .collect()
+    }
 }
@@ -1,20 +1,65 @@
-use crate::diagnostic::{Diagnostic, Severity};
-use crate::parser::Makefile;
-use crate::rules::{Rule, RuleCategory};
+use crate::diagnostic::{Diagnostic, Edit, Fix, LineIndex, Severity};
+use crate::parser::{AssignmentOperator, Makefile, Recipe, Variable};
+use crate::rules::{conditional_contexts, OptionKind, OptionSpec, Rule, RuleCategory};
+use anyhow::{bail, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthChar;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum NamingStyle {
+    /// No lowercase letters
     Upper,
+    /// No uppercase letters
     Lower,
+    /// No lowercase letters, with words separated by `_`
+    SnakeUpper,
+    /// No uppercase letters, with words separated by `_`
+    SnakeLower,
+    /// No uppercase letters, with words separated by `-`
+    Kebab,
 }
 
+const NAMING_STYLES: [NamingStyle; 5] = [
+    NamingStyle::Upper,
+    NamingStyle::Lower,
+    NamingStyle::SnakeUpper,
+    NamingStyle::SnakeLower,
+    NamingStyle::Kebab,
+];
+
 pub struct LineLength {
     max_length: usize,
+    /// Measure lines in terminal columns rather than bytes
+    display_width: bool,
 }
 
 impl LineLength {
     pub fn new(max_length: usize) -> Self {
-        Self { max_length }
+        Self {
+            max_length,
+            display_width: true,
+        }
+    }
+
+    /// Length of `line`, and the 1-based column and byte column of its first character
+    /// past the limit. The length and column are in display columns unless
+    /// `display_width` is off.
+    fn measure(&self, line: &str) -> (usize, usize, usize) {
+        if !self.display_width {
+            return (line.len(), self.max_length + 1, self.max_length + 1);
+        }
+
+        let mut width = 0;
+        let mut overflow = None;
+        for (index, c) in line.char_indices() {
+            if overflow.is_none() && width + c.width().unwrap_or(1) > self.max_length {
+                overflow = Some((width + 1, index + 1));
+            }
+            width += c.width().unwrap_or(1);
+        }
+        let (column, byte_column) = overflow.unwrap_or((width + 1, line.len() + 1));
+        (width, column, byte_column)
     }
 }
 
@@ -28,7 +73,11 @@ impl Rule for LineLength {
     }
 
     fn description(&self) -> &'static str {
-        "Lines should not exceed the configured maximum length for better readability."
+        "Lines should not exceed the configured maximum length for better readability. The \
+         `max` option sets the limit (120 by default, or `max_line_length` from a \
+         `.editorconfig` section matching the file). Lines are measured in display columns, \
+         so a wide CJK character counts twice, and the reported column is a display column \
+         too; set `display_width` to false to count bytes."
     }
 
     fn category(&self) -> RuleCategory {
@@ -36,26 +85,66 @@ impl Rule for LineLength {
     }
 
     fn check(&self, _makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let index = LineIndex::new(content);
         let mut diagnostics = Vec::new();
 
         for (line_num, line) in content.lines().enumerate() {
-            if line.len() > self.max_length {
-                diagnostics.push(Diagnostic::new(
-                    self.id(),
-                    Severity::Warning,
-                    format!(
-                        "Line length {} exceeds maximum of {}",
-                        line.len(),
-                        self.max_length
-                    ),
-                    line_num + 1,
-                    self.max_length + 1,
-                ));
+            let (length, column, byte_column) = self.measure(line);
+            if length > self.max_length {
+                // Columns may be display columns, so the byte offsets are set here
+                let offset = |column| index.offset(line_num + 1, column).unwrap_or_default();
+                diagnostics.push(
+                    Diagnostic::new(
+                        self.id(),
+                        Severity::Warning,
+                        format!(
+                            "Line length {} exceeds maximum of {}",
+                            length, self.max_length
+                        ),
+                        line_num + 1,
+                        column,
+                    )
+                    .with_span(line_num + 1, length + 1)
+                    .with_offsets(offset(byte_column), offset(line.len() + 1)),
+                );
             }
         }
 
         diagnostics
     }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new(
+                "max",
+                OptionKind::Integer,
+                self.max_length as i64,
+                "Maximum line length",
+            ),
+            OptionSpec::new(
+                "display_width",
+                OptionKind::Boolean,
+                self.display_width,
+                "Measure lines in display columns instead of bytes",
+            ),
+        ]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(max) = options.get("max") {
+            match max.as_integer() {
+                Some(max) if max > 0 => self.max_length = max as usize,
+                _ => bail!("option 'max' must be a positive integer"),
+            }
+        }
+        if let Some(display_width) = options.get("display_width") {
+            let Some(display_width) = display_width.as_bool() else {
+                bail!("option 'display_width' must be a boolean");
+            };
+            self.display_width = display_width;
+        }
+        Ok(())
+    }
 }
 
 pub struct VariableNaming {
@@ -88,24 +177,38 @@ impl Rule for VariableNaming {
     fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
 
-        for variable in makefile.variables.values() {
+        for variable in &makefile.assignments {
             if !matches_naming_style(&variable.name, self.style) {
                 let expected = naming_style_description(self.style);
-                diagnostics.push(Diagnostic::new(
-                    self.id(),
-                    Severity::Warning,
-                    format!(
-                        "Variable '{}' does not follow {} convention",
-                        variable.name, expected
-                    ),
-                    variable.line,
-                    variable.column,
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        self.id(),
+                        Severity::Warning,
+                        format!(
+                            "Variable '{}' does not follow {} convention",
+                            variable.name, expected
+                        ),
+                        variable.line,
+                        variable.column,
+                    )
+                    .with_span(variable.line, variable.column + variable.name.len()),
+                );
             }
         }
 
         diagnostics
     }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![naming_style_option(self.style)]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(style) = options.get("style") {
+            self.style = parse_naming_style(style)?;
+        }
+        Ok(())
+    }
 }
 
 pub struct TargetNaming {
@@ -135,32 +238,68 @@ impl Rule for TargetNaming {
         RuleCategory::Style
     }
 
-    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
 
         for rule in &makefile.rules {
+            let line = lines.get(rule.line - 1).copied().unwrap_or_default();
+            let mut search_from = rule.column - 1;
+
             for target in &rule.targets {
+                let column = match line
+                    .get(search_from..)
+                    .and_then(|rest| rest.find(target.as_str()))
+                {
+                    Some(pos) => {
+                        search_from += pos + target.len();
+                        search_from - target.len() + 1
+                    }
+                    None => rule.column,
+                };
+
                 if !target.starts_with('.') && !matches_naming_style(target, self.style) {
                     let expected = naming_style_description(self.style);
-                    diagnostics.push(Diagnostic::new(
-                        self.id(),
-                        Severity::Warning,
-                        format!("Target '{target}' does not follow {expected} convention"),
-                        rule.line,
-                        rule.column,
-                    ));
+                    diagnostics.push(
+                        Diagnostic::new(
+                            self.id(),
+                            Severity::Warning,
+                            format!("Target '{target}' does not follow {expected} convention"),
+                            rule.line,
+                            column,
+                        )
+                        .with_span(rule.line, column + target.len()),
+                    );
                 }
             }
         }
 
         diagnostics
     }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![naming_style_option(self.style)]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(style) = options.get("style") {
+            self.style = parse_naming_style(style)?;
+        }
+        Ok(())
+    }
 }
 
+/// Checks the letter case and word separators of `name`. Other characters, such as the
+/// `/` and `.` of file targets, are allowed in every style.
 fn matches_naming_style(name: &str, style: NamingStyle) -> bool {
+    let upper = name.chars().all(|c| !c.is_alphabetic() || c.is_uppercase());
+    let lower = name.chars().all(|c| !c.is_alphabetic() || c.is_lowercase());
     match style {
-        NamingStyle::Upper => name.chars().all(|c| !c.is_alphabetic() || c.is_uppercase()),
-        NamingStyle::Lower => name.chars().all(|c| !c.is_alphabetic() || c.is_lowercase()),
+        NamingStyle::Upper => upper,
+        NamingStyle::Lower => lower,
+        NamingStyle::SnakeUpper => upper && !name.contains('-'),
+        NamingStyle::SnakeLower => lower && !name.contains('-'),
+        NamingStyle::Kebab => lower && !name.contains('_'),
     }
 }
 
@@ -168,5 +307,1521 @@ fn naming_style_description(style: NamingStyle) -> &'static str {
     match style {
         NamingStyle::Upper => "UPPER_CASE",
         NamingStyle::Lower => "lower_case",
+        NamingStyle::SnakeUpper => "SCREAMING_SNAKE_CASE",
+        NamingStyle::SnakeLower => "snake_case",
+        NamingStyle::Kebab => "kebab-case",
+    }
+}
+
+/// Parses a `style` option, written the way [`naming_style_description`] names the style.
+fn parse_naming_style(value: &toml::Value) -> Result<NamingStyle> {
+    let style = NAMING_STYLES
+        .into_iter()
+        .find(|style| value.as_str() == Some(naming_style_description(*style)));
+    match style {
+        Some(style) => Ok(style),
+        None => {
+            let names: Vec<String> = NAMING_STYLES
+                .iter()
+                .map(|style| format!("\"{}\"", naming_style_description(*style)))
+                .collect();
+            bail!("option 'style' must be one of {}", names.join(", "))
+        }
+    }
+}
+
+fn naming_style_option(style: NamingStyle) -> OptionSpec {
+    OptionSpec::new(
+        "style",
+        OptionKind::String,
+        naming_style_description(style),
+        "Naming convention: \"UPPER_CASE\", \"lower_case\", \"SCREAMING_SNAKE_CASE\", \
+         \"snake_case\" or \"kebab-case\"",
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceStyle {
+    Paren,
+    Brace,
+}
+
+impl ReferenceStyle {
+    fn delimiters(self) -> (char, char) {
+        match self {
+            ReferenceStyle::Paren => ('(', ')'),
+            ReferenceStyle::Brace => ('{', '}'),
+        }
+    }
+}
+
+pub struct VariableReferenceStyle {
+    prefer: ReferenceStyle,
+}
+
+impl VariableReferenceStyle {
+    pub fn new(prefer: ReferenceStyle) -> Self {
+        Self { prefer }
+    }
+}
+
+impl Rule for VariableReferenceStyle {
+    fn id(&self) -> &'static str {
+        "MK105"
+    }
+
+    fn name(&self) -> &'static str {
+        "Inconsistent variable reference style"
+    }
+
+    fn description(&self) -> &'static str {
+        "Variable references should consistently use either `$(VAR)` or `${VAR}`. \
+         The preferred form is set with the `prefer` option (\"paren\" or \"brace\")."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, _makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let (preferred_open, preferred_close) = self.prefer.delimiters();
+
+        for (line_idx, line) in content.lines().enumerate() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            for (open, close) in find_references(line) {
+                let open_char = line.as_bytes()[open] as char;
+                if open_char == preferred_open {
+                    continue;
+                }
+
+                let line_num = line_idx + 1;
+                let fix = Fix::new(format!(
+                    "Use ${preferred_open}...{preferred_close} reference syntax"
+                ))
+                .add_edit(Edit::new(
+                    line_num,
+                    open + 1,
+                    line_num,
+                    open + 2,
+                    preferred_open.to_string(),
+                ))
+                .add_edit(Edit::new(
+                    line_num,
+                    close + 1,
+                    line_num,
+                    close + 2,
+                    preferred_close.to_string(),
+                ));
+
+                diagnostics.push(
+                    Diagnostic::new(
+                        self.id(),
+                        Severity::Warning,
+                        format!(
+                            "Variable reference '{}' should use ${preferred_open}...{preferred_close} syntax",
+                            &line[open - 1..=close]
+                        ),
+                        line_num,
+                        open,
+                    )
+                    .with_fix(fix),
+                );
+            }
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        let prefer = match self.prefer {
+            ReferenceStyle::Paren => "paren",
+            ReferenceStyle::Brace => "brace",
+        };
+        vec![OptionSpec::new(
+            "prefer",
+            OptionKind::String,
+            prefer,
+            "Preferred reference syntax: \"paren\" or \"brace\"",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(prefer) = options.get("prefer") {
+            self.prefer = match prefer.as_str() {
+                Some("paren") => ReferenceStyle::Paren,
+                Some("brace") => ReferenceStyle::Brace,
+                _ => bail!("option 'prefer' must be \"paren\" or \"brace\""),
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Finds `$(...)` and `${...}` references in a line, returning the byte offsets of the
+/// opening and matching closing delimiter. Nested references are reported as well.
+fn find_references(line: &str) -> Vec<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let mut references = Vec::new();
+    let mut i = 0;
+
+    while i + 1 < bytes.len() {
+        if bytes[i] != b'$' {
+            i += 1;
+            continue;
+        }
+
+        let (open, close) = match bytes[i + 1] {
+            b'$' => {
+                i += 2;
+                continue;
+            }
+            b'(' => (b'(', b')'),
+            b'{' => (b'{', b'}'),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        let mut depth = 0;
+        for (j, &byte) in bytes.iter().enumerate().skip(i + 1) {
+            if byte == open {
+                depth += 1;
+            } else if byte == close {
+                depth -= 1;
+                if depth == 0 {
+                    references.push((i + 1, j));
+                    break;
+                }
+            }
+        }
+
+        i += 2;
+    }
+
+    references
+}
+
+pub struct DuplicatePrerequisite;
+
+impl Rule for DuplicatePrerequisite {
+    fn id(&self) -> &'static str {
+        "MK106"
+    }
+
+    fn name(&self) -> &'static str {
+        "Duplicate prerequisite"
+    }
+
+    fn description(&self) -> &'static str {
+        "A prerequisite listed more than once for the same target is redundant. Normal and \
+         order-only prerequisites (after `|`) are checked separately."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+
+        for rule in &makefile.rules {
+            let (normal, normal_duplicates) = dedup_preserving_order(&rule.prerequisites);
+            let (order_only, order_only_duplicates) =
+                dedup_preserving_order(&rule.order_only_prerequisites);
+
+            let mut fix = lines
+                .get(rule.line - 1)
+                .and_then(|line| prerequisite_span(line))
+                .map(|(start, end)| {
+                    let mut replacement = String::new();
+                    for name in &normal {
+                        replacement.push(' ');
+                        replacement.push_str(name);
+                    }
+                    if !order_only.is_empty() {
+                        replacement.push_str(" |");
+                        for name in &order_only {
+                            replacement.push(' ');
+                            replacement.push_str(name);
+                        }
+                    }
+
+                    Fix::new("Remove duplicate prerequisites").add_edit(Edit::new(
+                        rule.line,
+                        start + 1,
+                        rule.line,
+                        end + 1,
+                        replacement,
+                    ))
+                });
+
+            for name in normal_duplicates.into_iter().chain(order_only_duplicates) {
+                let diagnostic = Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "Prerequisite '{name}' is listed more than once for target '{}'",
+                        rule.targets.join(" ")
+                    ),
+                    rule.line,
+                    rule.column,
+                );
+
+                // A single fix rewrites the whole list, so only the first diagnostic carries it
+                diagnostics.push(match fix.take() {
+                    Some(fix) => diagnostic.with_fix(fix),
+                    None => diagnostic,
+                });
+            }
+        }
+
+        diagnostics
+    }
+}
+
+fn dedup_preserving_order(names: &[String]) -> (Vec<&str>, Vec<&str>) {
+    let mut unique: Vec<&str> = Vec::new();
+    let mut duplicates: Vec<&str> = Vec::new();
+
+    for name in names {
+        if !unique.contains(&name.as_str()) {
+            unique.push(name);
+        } else if !duplicates.contains(&name.as_str()) {
+            duplicates.push(name);
+        }
+    }
+
+    (unique, duplicates)
+}
+
+/// Returns the byte range of a rule line's prerequisite list, from just after the colon
+/// to the end of the last prerequisite (excluding any trailing comment).
+fn prerequisite_span(line: &str) -> Option<(usize, usize)> {
+    let colon = line.find(':')?;
+    let start = if line[colon + 1..].starts_with(':') {
+        colon + 2
+    } else {
+        colon + 1
+    };
+    let rest = &line[start..];
+    let rest = rest.split('#').next().unwrap_or(rest);
+    Some((start, start + rest.trim_end().len()))
+}
+
+const DEFAULT_TODO_MARKERS: &[&str] = &["TODO", "FIXME", "XXX", "HACK"];
+
+pub struct TodoComment {
+    markers: Vec<String>,
+    case_sensitive: bool,
+    pattern: Regex,
+}
+
+impl TodoComment {
+    pub fn new() -> Self {
+        let markers: Vec<String> = DEFAULT_TODO_MARKERS.iter().map(|m| m.to_string()).collect();
+        let pattern = marker_pattern(&markers, true);
+        Self {
+            markers,
+            case_sensitive: true,
+            pattern,
+        }
+    }
+}
+
+impl Default for TodoComment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for TodoComment {
+    fn id(&self) -> &'static str {
+        "MK107"
+    }
+
+    fn name(&self) -> &'static str {
+        "Comment contains a TODO marker"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports comments containing markers such as TODO, FIXME, XXX or HACK so leftover \
+         work stays visible. The marker list is set with the `markers` option and matching \
+         is case-sensitive unless `case_sensitive` is false."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for comment in &makefile.comments {
+            for found in self.pattern.find_iter(&comment.text) {
+                diagnostics.push(Diagnostic::new(
+                    self.id(),
+                    Severity::Info,
+                    format!(
+                        "{} marker: {}",
+                        found.as_str(),
+                        comment.text[found.start()..].trim_end()
+                    ),
+                    comment.line,
+                    comment.column + 1 + found.start(),
+                ));
+            }
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new(
+                "markers",
+                OptionKind::StringArray,
+                self.markers.clone(),
+                "Words that mark a comment as a to-do",
+            ),
+            OptionSpec::new(
+                "case_sensitive",
+                OptionKind::Boolean,
+                self.case_sensitive,
+                "Whether markers only match with the same case",
+            ),
+        ]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(markers) = options.get("markers") {
+            let Some(markers) = markers.as_array().and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect()
+            }) else {
+                bail!("option 'markers' must be an array of strings");
+            };
+            self.markers = markers;
+        }
+        if let Some(case_sensitive) = options.get("case_sensitive") {
+            let Some(case_sensitive) = case_sensitive.as_bool() else {
+                bail!("option 'case_sensitive' must be a boolean");
+            };
+            self.case_sensitive = case_sensitive;
+        }
+
+        self.pattern = marker_pattern(&self.markers, self.case_sensitive);
+        Ok(())
+    }
+}
+
+fn marker_pattern(markers: &[String], case_sensitive: bool) -> Regex {
+    let alternatives: Vec<String> = markers.iter().map(|m| regex::escape(m)).collect();
+    let flags = if case_sensitive { "" } else { "(?i)" };
+    Regex::new(&format!(r"{flags}\b(?:{})\b", alternatives.join("|"))).unwrap()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssignmentSpacing {
+    /// `VAR = value`
+    Spaced,
+    /// `VAR=value`
+    Tight,
+}
+
+pub struct AssignmentSpacingStyle {
+    spacing: AssignmentSpacing,
+}
+
+impl AssignmentSpacingStyle {
+    pub fn new(spacing: AssignmentSpacing) -> Self {
+        Self { spacing }
+    }
+}
+
+impl Rule for AssignmentSpacingStyle {
+    fn id(&self) -> &'static str {
+        "MK108"
+    }
+
+    fn name(&self) -> &'static str {
+        "Whitespace around assignment operator"
+    }
+
+    fn description(&self) -> &'static str {
+        "Assignment operators should consistently be surrounded by spaces (`VAR = value`) \
+         or not (`VAR=value`), as set by the `spacing` option (\"spaced\" or \"tight\")."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+
+        for variable in &makefile.assignments {
+            let Some(line) = lines.get(variable.line - 1) else {
+                continue;
+            };
+            let operator = variable.operator.as_str();
+            let op_start = variable.operator_column - 1;
+            let op_end = op_start + operator.len();
+            if line.get(op_start..op_end) != Some(operator) {
+                continue;
+            }
+
+            let name_end = line[..op_start].trim_end().len();
+            let value_start = line.len() - line[op_end..].trim_start().len();
+            let has_value = value_start < line.len();
+            let spaced_before = name_end < op_start;
+            let spaced_after = value_start > op_end;
+
+            let (valid, replacement) = match self.spacing {
+                AssignmentSpacing::Spaced => (
+                    spaced_before && (spaced_after || !has_value),
+                    if has_value {
+                        format!(" {operator} ")
+                    } else {
+                        format!(" {operator}")
+                    },
+                ),
+                AssignmentSpacing::Tight => (
+                    !spaced_before && (!spaced_after || !has_value),
+                    operator.to_string(),
+                ),
+            };
+            if valid {
+                continue;
+            }
+
+            let fix =
+                Fix::new("Normalize whitespace around assignment operator").add_edit(Edit::new(
+                    variable.line,
+                    name_end + 1,
+                    variable.line,
+                    value_start + 1,
+                    replacement,
+                ));
+
+            let expected = match self.spacing {
+                AssignmentSpacing::Spaced => "spaces around",
+                AssignmentSpacing::Tight => "no spaces around",
+            };
+            diagnostics.push(
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "Assignment to '{}' should have {expected} '{operator}'",
+                        variable.name
+                    ),
+                    variable.line,
+                    variable.operator_column,
+                )
+                .with_fix(fix),
+            );
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        let spacing = match self.spacing {
+            AssignmentSpacing::Spaced => "spaced",
+            AssignmentSpacing::Tight => "tight",
+        };
+        vec![OptionSpec::new(
+            "spacing",
+            OptionKind::String,
+            spacing,
+            "Whitespace around assignment operators: \"spaced\" or \"tight\"",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(spacing) = options.get("spacing") {
+            self.spacing = match spacing.as_str() {
+                Some("spaced") => AssignmentSpacing::Spaced,
+                Some("tight") => AssignmentSpacing::Tight,
+                _ => bail!("option 'spacing' must be \"spaced\" or \"tight\""),
+            };
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EchoSilence {
+    /// Follow whichever style most echo commands in the file use
+    Majority,
+    /// `@echo ...`
+    Silent,
+    /// `echo ...`
+    Verbose,
+}
+
+pub struct EchoPrefixConsistency {
+    prefer: EchoSilence,
+    threshold: f64,
+}
+
+impl EchoPrefixConsistency {
+    pub fn new() -> Self {
+        Self {
+            prefer: EchoSilence::Majority,
+            threshold: 0.5,
+        }
+    }
+}
+
+impl Default for EchoPrefixConsistency {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for EchoPrefixConsistency {
+    fn id(&self) -> &'static str {
+        "MK109"
+    }
+
+    fn name(&self) -> &'static str {
+        "Inconsistent @ prefix on echo commands"
+    }
+
+    fn description(&self) -> &'static str {
+        "`echo` and `printf` commands should consistently use the `@` prefix, or consistently \
+         omit it. By default the minority style is reported once the majority exceeds the \
+         `threshold` share (0.5); the `prefer` option (\"majority\", \"silent\" or \
+         \"verbose\") enforces a fixed style instead."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let echoes: Vec<&Recipe> = makefile
+            .rules
+            .iter()
+            .flat_map(|rule| &rule.recipes)
+            .filter(|recipe| {
+                matches!(
+                    recipe.command.split_whitespace().next(),
+                    Some("echo" | "printf")
+                )
+            })
+            .collect();
+        if echoes.is_empty() {
+            return Vec::new();
+        }
+
+        let silent = echoes.iter().filter(|recipe| recipe.silent).count();
+        let expect_silent = match self.prefer {
+            EchoSilence::Silent => true,
+            EchoSilence::Verbose => false,
+            EchoSilence::Majority => {
+                let share = silent as f64 / echoes.len() as f64;
+                if share > self.threshold {
+                    true
+                } else if 1.0 - share > self.threshold {
+                    false
+                } else {
+                    return Vec::new();
+                }
+            }
+        };
+
+        let mut diagnostics = Vec::new();
+        for recipe in echoes
+            .iter()
+            .filter(|recipe| recipe.silent != expect_silent)
+        {
+            let prefix_column = recipe.indentation.len() + 1;
+            let (message, fix) = if expect_silent {
+                (
+                    "Echo command is missing the @ prefix used elsewhere",
+                    Fix::new("Add @ prefix").add_edit(Edit::new(
+                        recipe.line,
+                        prefix_column,
+                        recipe.line,
+                        prefix_column,
+                        "@".to_string(),
+                    )),
+                )
+            } else {
+                let at_column = prefix_column + recipe.prefix.find('@').unwrap_or(0);
+                (
+                    "Echo command has an @ prefix not used elsewhere",
+                    Fix::new("Remove @ prefix").add_edit(Edit::new(
+                        recipe.line,
+                        at_column,
+                        recipe.line,
+                        at_column + 1,
+                        String::new(),
+                    )),
+                )
+            };
+
+            diagnostics.push(
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    message,
+                    recipe.line,
+                    prefix_column,
+                )
+                .with_fix(fix),
+            );
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        let prefer = match self.prefer {
+            EchoSilence::Majority => "majority",
+            EchoSilence::Silent => "silent",
+            EchoSilence::Verbose => "verbose",
+        };
+        vec![
+            OptionSpec::new(
+                "prefer",
+                OptionKind::String,
+                prefer,
+                "Expected echo style: \"majority\", \"silent\" or \"verbose\"",
+            ),
+            OptionSpec::new(
+                "threshold",
+                OptionKind::Float,
+                self.threshold,
+                "Share of echo lines that makes a style the majority",
+            ),
+        ]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(prefer) = options.get("prefer") {
+            self.prefer = match prefer.as_str() {
+                Some("majority") => EchoSilence::Majority,
+                Some("silent") => EchoSilence::Silent,
+                Some("verbose") => EchoSilence::Verbose,
+                _ => bail!("option 'prefer' must be \"majority\", \"silent\" or \"verbose\""),
+            };
+        }
+        if let Some(threshold) = options.get("threshold") {
+            match threshold.as_float() {
+                Some(threshold) if (0.5..1.0).contains(&threshold) => self.threshold = threshold,
+                _ => bail!("option 'threshold' must be a number from 0.5 up to 1.0"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SilenceStyle {
+    /// List fully silent targets under `.SILENT`
+    SilentTarget,
+    /// Prefix each recipe line with `@`
+    Prefix,
+}
+
+pub struct SilentTarget {
+    prefer: SilenceStyle,
+}
+
+impl SilentTarget {
+    pub fn new() -> Self {
+        Self {
+            prefer: SilenceStyle::SilentTarget,
+        }
+    }
+}
+
+impl Default for SilentTarget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for SilentTarget {
+    fn id(&self) -> &'static str {
+        "MK110"
+    }
+
+    fn name(&self) -> &'static str {
+        "Silence whole targets consistently"
+    }
+
+    fn description(&self) -> &'static str {
+        "A target whose recipe lines all carry the `@` prefix can be listed under `.SILENT` \
+         instead. With `prefer = \"prefix\"` the rule reports `.SILENT` declarations instead, \
+         for projects that want every silenced command marked explicitly."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let declarations = find_silent_declarations(content);
+
+        if self.prefer == SilenceStyle::Prefix {
+            return declarations
+                .iter()
+                .map(|(line, column, _)| {
+                    Diagnostic::new(
+                        self.id(),
+                        Severity::Info,
+                        "Use @ prefixes on recipe lines instead of .SILENT",
+                        *line,
+                        *column,
+                    )
+                })
+                .collect();
+        }
+
+        // A bare `.SILENT:` already silences every target
+        if declarations
+            .iter()
+            .any(|(_, _, targets)| targets.is_empty())
+        {
+            return Vec::new();
+        }
+        let silenced: Vec<&str> = declarations
+            .iter()
+            .flat_map(|(_, _, targets)| targets.iter().map(String::as_str))
+            .collect();
+
+        makefile
+            .rules
+            .iter()
+            .filter(|rule| {
+                rule.recipes.len() > 1 && rule.recipes.iter().all(|recipe| recipe.silent)
+            })
+            .filter(|rule| {
+                !rule
+                    .targets
+                    .iter()
+                    .all(|target| silenced.contains(&target.as_str()))
+            })
+            .map(|rule| {
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Info,
+                    format!(
+                        "Every recipe line of '{}' has an @ prefix; consider listing it under .SILENT",
+                        rule.targets.join(" ")
+                    ),
+                    rule.line,
+                    rule.column,
+                )
+            })
+            .collect()
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        let prefer = match self.prefer {
+            SilenceStyle::SilentTarget => "silent-target",
+            SilenceStyle::Prefix => "prefix",
+        };
+        vec![OptionSpec::new(
+            "prefer",
+            OptionKind::String,
+            prefer,
+            "How to silence whole targets: \"silent-target\" or \"prefix\"",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(prefer) = options.get("prefer") {
+            self.prefer = match prefer.as_str() {
+                Some("silent-target") => SilenceStyle::SilentTarget,
+                Some("prefix") => SilenceStyle::Prefix,
+                _ => bail!("option 'prefer' must be \"silent-target\" or \"prefix\""),
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Returns the line, column and listed targets of every `.SILENT:` declaration.
+fn find_silent_declarations(content: &str) -> Vec<(usize, usize, Vec<String>)> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let trimmed = line.trim_start();
+            let targets = trimmed.strip_prefix(".SILENT:")?;
+            let targets = targets.split('#').next().unwrap_or(targets);
+            Some((
+                idx + 1,
+                line.len() - trimmed.len() + 1,
+                targets.split_whitespace().map(String::from).collect(),
+            ))
+        })
+        .collect()
+}
+
+pub struct BlankLineInRecipe;
+
+impl Rule for BlankLineInRecipe {
+    fn id(&self) -> &'static str {
+        "MK111"
+    }
+
+    fn name(&self) -> &'static str {
+        "Blank line inside a recipe"
+    }
+
+    fn description(&self) -> &'static str {
+        "A blank line between the recipe lines of a rule makes the recipe look like it ends \
+         there, so later commands are easily mistaken for a separate block or dropped when \
+         editing. Keep each recipe contiguous."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        makefile
+            .rules
+            .iter()
+            .flat_map(|rule| rule.blank_lines.iter().map(move |line| (rule, *line)))
+            .map(|(rule, line)| {
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "Blank line inside the recipe of '{}'",
+                        rule.targets.join(" ")
+                    ),
+                    line,
+                    1,
+                )
+                .with_fix(Fix::new("Remove blank line").add_edit(Edit::new(
+                    line,
+                    1,
+                    line + 1,
+                    1,
+                    String::new(),
+                )))
+            })
+            .collect()
+    }
+}
+
+pub struct MultiplePhony;
+
+impl Rule for MultiplePhony {
+    fn id(&self) -> &'static str {
+        "MK112"
+    }
+
+    fn name(&self) -> &'static str {
+        "Multiple .PHONY declarations"
+    }
+
+    fn description(&self) -> &'static str {
+        "Make merges every `.PHONY:` line, but declarations scattered across the file are \
+         easy to miss when adding or renaming targets. Declare all phony targets in one place."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let Some((&first, rest)) = makefile.phony_lines.split_first() else {
+            return Vec::new();
+        };
+        let lines: Vec<&str> = content.lines().collect();
+
+        rest.iter()
+            .map(|&line| {
+                let column = lines
+                    .get(line - 1)
+                    .map_or(1, |text| text.len() - text.trim_start().len() + 1);
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        ".PHONY is already declared on line {first}; consolidate the declarations"
+                    ),
+                    line,
+                    column,
+                )
+            })
+            .collect()
+    }
+}
+
+const DEFAULT_MAX_PREREQUISITES: usize = 8;
+
+pub struct LongPrerequisiteList {
+    max: usize,
+}
+
+impl LongPrerequisiteList {
+    pub fn new() -> Self {
+        Self {
+            max: DEFAULT_MAX_PREREQUISITES,
+        }
+    }
+}
+
+impl Default for LongPrerequisiteList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for LongPrerequisiteList {
+    fn id(&self) -> &'static str {
+        "MK113"
+    }
+
+    fn name(&self) -> &'static str {
+        "Too many prerequisites on one line"
+    }
+
+    fn description(&self) -> &'static str {
+        "A long prerequisite list on a single line is hard to read and produces noisy diffs. \
+         Split it across backslash-continued lines, one or a few prerequisites per line. The \
+         `max` option sets how many prerequisites a line may hold (default 8)."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            let Some(line) = lines.get(rule.line - 1) else {
+                continue;
+            };
+            let Some((start, end)) = prerequisite_span(line) else {
+                continue;
+            };
+
+            // Only the prerequisites on the rule's own physical line, before any inline recipe
+            let list = line[start..end].split(';').next().unwrap_or_default();
+            let count = list
+                .split_whitespace()
+                .filter(|word| *word != "|" && *word != "\\")
+                .count();
+            if count > self.max {
+                diagnostics.push(Diagnostic::new(
+                    self.id(),
+                    Severity::Info,
+                    format!(
+                        "{count} prerequisites on one line (max {}); split them across continuation lines",
+                        self.max
+                    ),
+                    rule.line,
+                    rule.column,
+                ));
+            }
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "max",
+            OptionKind::Integer,
+            self.max as i64,
+            "Maximum number of prerequisites on one line",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(max) = options.get("max") {
+            match max.as_integer() {
+                Some(max) if max > 0 => self.max = max as usize,
+                _ => bail!("option 'max' must be a positive integer"),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct UndocumentedPhony;
+
+impl Rule for UndocumentedPhony {
+    fn id(&self) -> &'static str {
+        "MK114"
+    }
+
+    fn name(&self) -> &'static str {
+        "Phony target without a help comment"
+    }
+
+    fn description(&self) -> &'static str {
+        "Phony targets are the commands users run, so each should have a `## ` comment on \
+         the line above it, which `make help` generators turn into usage text. This rule is \
+         opt-in: enable it in the config file to use it."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn opt_in(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for phony in &makefile.phonies {
+            let mut rules = makefile
+                .rules
+                .iter()
+                .filter(|rule| rule.targets.contains(phony))
+                .peekable();
+            let Some(first) = rules.peek().copied() else {
+                continue;
+            };
+            if rules.any(|rule| rule.doc.is_some()) {
+                continue;
+            }
+
+            diagnostics.push(Diagnostic::new(
+                self.id(),
+                Severity::Info,
+                format!("Phony target '{phony}' has no '## ' help comment"),
+                first.line,
+                first.column,
+            ));
+        }
+
+        diagnostics
+    }
+}
+
+pub struct EmbeddedTab;
+
+impl Rule for EmbeddedTab {
+    fn id(&self) -> &'static str {
+        "MK115"
+    }
+
+    fn name(&self) -> &'static str {
+        "Tab inside a rule or assignment line"
+    }
+
+    fn description(&self) -> &'static str {
+        "Only the tab that starts a recipe line has a meaning to Make. A tab between \
+         prerequisites or inside a variable value is usually an editor accident, and it ends \
+         up in the expanded value. Use spaces instead."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut line_numbers: Vec<usize> = makefile.rules.iter().map(|rule| rule.line).collect();
+        // The body of a `define` block may hold recipe lines
+        line_numbers.extend(
+            makefile
+                .assignments
+                .iter()
+                .filter(|variable| variable.value_start_column > 1)
+                .map(|variable| variable.line),
+        );
+        line_numbers.sort_unstable();
+        line_numbers.dedup();
+
+        let mut diagnostics = Vec::new();
+        for line_number in line_numbers {
+            let Some(line) = lines.get(line_number - 1) else {
+                continue;
+            };
+            // Leading indentation is MK004's concern, and tabs in comments are harmless
+            let indent = line.len() - line.trim_start().len();
+            let code = line.split('#').next().unwrap_or_default();
+
+            let mut search_from = indent;
+            while let Some(offset) = code.get(search_from..).and_then(|rest| rest.find('\t')) {
+                let start = search_from + offset;
+                let end =
+                    start + code[start..].len() - code[start..].trim_start_matches('\t').len();
+                search_from = end;
+
+                diagnostics.push(
+                    Diagnostic::new(
+                        self.id(),
+                        Severity::Warning,
+                        "Tab character outside recipe indentation",
+                        line_number,
+                        start + 1,
+                    )
+                    .with_span(line_number, end + 1)
+                    .with_fix(Fix::new("Replace with a space").add_edit(Edit::new(
+                        line_number,
+                        start + 1,
+                        line_number,
+                        end + 1,
+                        " ".to_string(),
+                    ))),
+                );
+            }
+        }
+
+        diagnostics
+    }
+}
+
+pub struct MissingFinalNewline;
+
+impl Rule for MissingFinalNewline {
+    fn id(&self) -> &'static str {
+        "MK116"
+    }
+
+    fn name(&self) -> &'static str {
+        "Missing newline at end of file"
+    }
+
+    fn description(&self) -> &'static str {
+        "POSIX defines a line as ending with a newline, so a file whose last line lacks one \
+         is not a proper text file. Tools like `cat` and `diff` handle such files awkwardly."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, _makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        if content.is_empty() || content.ends_with('\n') {
+            return Vec::new();
+        }
+
+        let line_number = content.lines().count();
+        let column = content.lines().last().unwrap_or_default().len() + 1;
+        let newline = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        vec![Diagnostic::new(
+            self.id(),
+            Severity::Warning,
+            "File does not end with a newline",
+            line_number,
+            column,
+        )
+        .with_fix(Fix::new("Add a final newline").add_edit(Edit::new(
+            line_number,
+            column,
+            line_number,
+            column,
+            newline.to_string(),
+        )))]
+    }
+}
+
+const DEFAULT_MAX_RECIPE_LENGTH: usize = 200;
+
+pub struct LongRecipeCommand {
+    max: usize,
+}
+
+impl LongRecipeCommand {
+    pub fn new() -> Self {
+        Self {
+            max: DEFAULT_MAX_RECIPE_LENGTH,
+        }
+    }
+}
+
+impl Default for LongRecipeCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for LongRecipeCommand {
+    fn id(&self) -> &'static str {
+        "MK117"
+    }
+
+    fn name(&self) -> &'static str {
+        "Recipe command too long"
+    }
+
+    fn description(&self) -> &'static str {
+        "A very long recipe command, such as a giant compiler invocation, is hard to review and \
+         can run into the system's argument length limit. The whole command counts, with its \
+         backslash-continued lines joined. The `max` option sets the allowed length (default 200)."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for rule in &makefile.rules {
+            for recipe in &rule.recipes {
+                let length = recipe.command.chars().count();
+                if length > self.max {
+                    diagnostics.push(Diagnostic::new(
+                        self.id(),
+                        Severity::Info,
+                        format!(
+                            "Recipe command is {length} characters long (max {}); split it across `\\` continuation lines or move it into a script",
+                            self.max
+                        ),
+                        recipe.line,
+                        recipe.column,
+                    ));
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "max",
+            OptionKind::Integer,
+            self.max as i64,
+            "Maximum length of a recipe command, with continuation lines joined",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(max) = options.get("max") {
+            match max.as_integer() {
+                Some(max) if max > 0 => self.max = max as usize,
+                _ => bail!("option 'max' must be a positive integer"),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct EchoProgress {
+    silent_only: bool,
+}
+
+impl EchoProgress {
+    pub fn new() -> Self {
+        Self { silent_only: true }
+    }
+}
+
+impl Default for EchoProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule for EchoProgress {
+    fn id(&self) -> &'static str {
+        "MK118"
+    }
+
+    fn name(&self) -> &'static str {
+        "Progress message printed with echo"
+    }
+
+    fn description(&self) -> &'static str {
+        "A recipe line that only echoes a literal message, like `@echo \"building\"`, spawns a \
+         shell just to print it; `$(info building)` prints the message from Make itself. Make \
+         expands the whole recipe before running it, so `$(info)` output comes before the \
+         recipe's commands. By default only `@echo` lines are reported; set `silent_only` to \
+         false to include plain `echo`. This rule is opt-in: enable it in the config file to \
+         use it."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn opt_in(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for recipe in makefile.rules.iter().flat_map(|rule| &rule.recipes) {
+            if self.silent_only && !recipe.silent {
+                continue;
+            }
+            let Some(message) = echoed_literal(&recipe.command) else {
+                continue;
+            };
+
+            diagnostics.push(Diagnostic::new(
+                self.id(),
+                Severity::Info,
+                format!("Use '$(info {message})' instead of echo to print a progress message"),
+                recipe.line,
+                recipe.column,
+            ));
+        }
+
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "silent_only",
+            OptionKind::Boolean,
+            self.silent_only,
+            "Only report echo lines with the @ prefix",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(silent_only) = options.get("silent_only") {
+            let Some(silent_only) = silent_only.as_bool() else {
+                bail!("option 'silent_only' must be a boolean");
+            };
+            self.silent_only = silent_only;
+        }
+        Ok(())
+    }
+}
+
+/// The message of an `echo` command that prints a literal, unquoted. Commands with
+/// options, shell variables, pipes, redirections or further commands yield `None`.
+fn echoed_literal(command: &str) -> Option<&str> {
+    let text = command.strip_prefix("echo")?;
+    if !text.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = text.trim();
+    if text.is_empty()
+        || text.starts_with('-')
+        || text.contains("$$")
+        || text.contains(['|', '>', '<', ';', '&', '`', '\\'])
+    {
+        return None;
+    }
+
+    // A single quoted string, or words without quotes
+    let unquoted = ['"', '\''].into_iter().find_map(|quote| {
+        text.strip_prefix(quote)?
+            .strip_suffix(quote)
+            .filter(|inner| !inner.contains(quote))
+    });
+    match unquoted {
+        Some(inner) => Some(inner),
+        None if text.contains(['"', '\'']) => None,
+        None => Some(text),
+    }
+}
+
+pub struct ShadowedVariable;
+
+impl Rule for ShadowedVariable {
+    fn id(&self) -> &'static str {
+        "MK119"
+    }
+
+    fn name(&self) -> &'static str {
+        "Variable assigned again before its value is used"
+    }
+
+    fn description(&self) -> &'static str {
+        "When a variable is assigned with `=` or `:=` and then assigned again before anything \
+         reads it, the first value is silently thrown away. Usually one of the assignments is \
+         a leftover; remove it, or use `+=` or `?=` if the second was meant to extend or \
+         default the first. Assignments in different branches of a conditional are exempt."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Style
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let contexts = conditional_contexts(content);
+        let context = |line: usize| contexts.get(line - 1).cloned().unwrap_or_default();
+        let mut diagnostics = Vec::new();
+        let mut previous: HashMap<&str, &Variable> = HashMap::new();
+
+        for variable in &makefile.assignments {
+            let replaces = matches!(
+                variable.operator,
+                AssignmentOperator::Recursive
+                    | AssignmentOperator::Simple
+                    | AssignmentOperator::PosixSimple
+            );
+            if let Some(earlier) = previous.insert(&variable.name, variable) {
+                // Any reference from the earlier assignment on, including the new value's
+                let used = makefile.references.iter().any(|r| {
+                    r.name == variable.name && r.line > earlier.line && r.line <= variable.line
+                });
+                if replaces && !used && context(earlier.line) == context(variable.line) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            self.id(),
+                            Severity::Warning,
+                            format!(
+                                "'{}' is assigned again before the value from line {} is used",
+                                variable.name, earlier.line
+                            ),
+                            variable.line,
+                            variable.column,
+                        )
+                        .with_span(variable.line, variable.column + variable.name.len()),
+                    );
+                }
+            }
+        }
+
+        diagnostics
     }
 }
@@ -1,6 +1,8 @@
 use crate::diagnostic::{Diagnostic, Edit, Fix, Severity};
-use crate::parser::Makefile;
-use crate::rules::{Rule, RuleCategory};
+use crate::parser::{AssignmentOperator, Makefile};
+use crate::rules::{levenshtein, OptionKind, OptionSpec, Rule, RuleCategory};
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
 
 pub struct TabInRecipe;
 
@@ -22,12 +24,16 @@ impl Rule for TabInRecipe {
         RuleCategory::Syntax
     }
 
+    fn fixable(&self) -> bool {
+        true
+    }
+
     fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
 
         for rule in &makefile.rules {
             for recipe in &rule.recipes {
-                if !recipe.indentation.starts_with('\t') {
+                if !recipe.inline && !recipe.indentation.starts_with('\t') {
                     let fix = Fix::new("Replace spaces with tab").add_edit(Edit::new(
                         recipe.line,
                         1,
@@ -54,7 +60,23 @@ impl Rule for TabInRecipe {
     }
 }
 
-pub struct InvalidVariableSyntax;
+pub struct InvalidVariableSyntax {
+    rename_references: bool,
+}
+
+impl InvalidVariableSyntax {
+    pub fn new() -> Self {
+        Self {
+            rename_references: true,
+        }
+    }
+}
+
+impl Default for InvalidVariableSyntax {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Rule for InvalidVariableSyntax {
     fn id(&self) -> &'static str {
@@ -66,7 +88,215 @@ impl Rule for InvalidVariableSyntax {
     }
 
     fn description(&self) -> &'static str {
-        "Variable names should follow Make conventions and not contain invalid characters."
+        "Variable names should follow Make conventions and not contain invalid characters. \
+         The unsafe fix replaces invalid characters with `_`, and also renames `$(VAR)` and \
+         `${VAR}` references unless the `rename_references` option is false."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Syntax
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for variable in &makefile.assignments {
+            if is_valid_variable_name(&variable.name) {
+                continue;
+            }
+
+            let sanitized = sanitize_variable_name(&variable.name);
+            let name_end = variable.column + variable.name.len();
+            // Renaming changes which variable every reference resolves to
+            let mut fix = Fix::new(format!("Rename to '{sanitized}'"))
+                .as_unsafe()
+                .add_edit(Edit::new(
+                    variable.line,
+                    variable.column,
+                    variable.line,
+                    name_end,
+                    sanitized.clone(),
+                ));
+
+            if self.rename_references {
+                for (line_idx, line) in content.lines().enumerate() {
+                    for (open, close) in [("$(", ')'), ("${", '}')] {
+                        let reference = format!("{open}{}{close}", variable.name);
+                        for (pos, _) in line.match_indices(&reference) {
+                            let start = pos + open.len() + 1;
+                            fix = fix.add_edit(Edit::new(
+                                line_idx + 1,
+                                start,
+                                line_idx + 1,
+                                start + variable.name.len(),
+                                sanitized.clone(),
+                            ));
+                        }
+                    }
+                }
+            }
+
+            diagnostics.push(
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Error,
+                    format!("Invalid variable name: '{}'", variable.name),
+                    variable.line,
+                    variable.column,
+                )
+                .with_span(variable.line, name_end)
+                .with_fix(fix),
+            );
+        }
+
+        diagnostics.sort_by_key(|d| d.line);
+        diagnostics
+    }
+
+    fn options(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "rename_references",
+            OptionKind::Boolean,
+            self.rename_references,
+            "Whether the fix also renames references to the variable",
+        )]
+    }
+
+    fn configure(&mut self, options: &HashMap<String, toml::Value>) -> Result<()> {
+        if let Some(rename) = options.get("rename_references") {
+            let Some(rename) = rename.as_bool() else {
+                bail!("option 'rename_references' must be a boolean");
+            };
+            self.rename_references = rename;
+        }
+        Ok(())
+    }
+}
+
+fn sanitize_variable_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+pub struct TabOutsideRecipe;
+
+impl Rule for TabOutsideRecipe {
+    fn id(&self) -> &'static str {
+        "MK004"
+    }
+
+    fn name(&self) -> &'static str {
+        "Tab indentation outside a recipe"
+    }
+
+    fn description(&self) -> &'static str {
+        "Lines starting with a tab are recipe lines. A tab-indented command that doesn't \
+         follow a rule makes Make fail with \"recipe commences before first target\"."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Syntax
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let rule_lines: HashSet<usize> = makefile
+            .rules
+            .iter()
+            .map(|rule| rule.line)
+            .chain(makefile.dotted_rule_lines.iter().copied())
+            .collect();
+        let mut parsed_lines: HashSet<usize> = makefile
+            .rules
+            .iter()
+            .flat_map(|rule| &rule.recipes)
+            .flat_map(|recipe| recipe.line..=recipe.end_line)
+            .collect();
+        parsed_lines.extend(makefile.assignments.iter().map(|v| v.line));
+        parsed_lines.extend(makefile.includes.iter().map(|include| include.line));
+        parsed_lines.extend(makefile.comments.iter().map(|comment| comment.line));
+
+        let mut diagnostics = Vec::new();
+        let mut in_recipe = false;
+        let mut continued = false;
+        let mut define_depth: usize = 0;
+
+        for (index, line) in content.lines().enumerate() {
+            let line_number = index + 1;
+            let is_continuation = continued;
+            continued = line.ends_with('\\');
+
+            let trimmed = line.trim_start();
+            if is_continuation || trimmed.is_empty() {
+                continue;
+            }
+
+            // Bodies of `define` blocks are variable values, which may contain recipes
+            let mut words = trimmed.split_whitespace();
+            let keyword = match words.next() {
+                Some("export" | "override") => words.next(),
+                keyword => keyword,
+            };
+            match keyword {
+                Some("define") => define_depth += 1,
+                Some("endef") => define_depth = define_depth.saturating_sub(1),
+                _ => {}
+            }
+            if define_depth > 0 || keyword == Some("endef") {
+                continue;
+            }
+
+            if rule_lines.contains(&line_number) {
+                in_recipe = true;
+            } else if line.starts_with('\t') {
+                // Tab-indented assignments and directives outside a rule are valid
+                if !in_recipe && !parsed_lines.contains(&line_number) && !is_directive(trimmed) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            self.id(),
+                            Severity::Error,
+                            "Tab-indented line is not part of any rule's recipe",
+                            line_number,
+                            1,
+                        )
+                        .with_span(line_number, line.len() + 1),
+                    );
+                }
+            } else if !trimmed.starts_with('#') && !is_conditional(trimmed) {
+                // Recipes continue across blank lines, comments and conditionals
+                in_recipe = false;
+            }
+        }
+
+        diagnostics
+    }
+}
+
+pub struct AutomaticVariableAssignment;
+
+impl Rule for AutomaticVariableAssignment {
+    fn id(&self) -> &'static str {
+        "MK005"
+    }
+
+    fn name(&self) -> &'static str {
+        "Assignment to an automatic variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "Automatic variables such as `$@`, `$<` and `$^` are set by Make for each rule, and \
+         `MAKEFILE_LIST` is maintained by Make itself. Assigning to them has no useful effect \
+         and is almost always a mistake."
     }
 
     fn category(&self) -> RuleCategory {
@@ -76,22 +306,55 @@ impl Rule for InvalidVariableSyntax {
     fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
         let mut diagnostics = Vec::new();
 
-        for variable in makefile.variables.values() {
-            if !is_valid_variable_name(&variable.name) {
+        for variable in &makefile.assignments {
+            if is_automatic_variable(variable.name.trim_start_matches('$')) {
                 diagnostics.push(Diagnostic::new(
                     self.id(),
                     Severity::Error,
-                    format!("Invalid variable name: '{}'", variable.name),
+                    format!(
+                        "Assignment to automatic variable '{}' has no effect",
+                        variable.name
+                    ),
                     variable.line,
                     variable.column,
                 ));
             }
         }
 
+        diagnostics.sort_by_key(|d| d.line);
         diagnostics
     }
 }
 
+/// Matches the automatic variables, including their `D`/`F` directory and file variants,
+/// and the variables Make reserves for itself.
+fn is_automatic_variable(name: &str) -> bool {
+    let base = name
+        .strip_suffix(['D', 'F'])
+        .filter(|base| base.len() == 1)
+        .unwrap_or(name);
+
+    matches!(base, "@" | "<" | "^" | "*" | "?" | "+" | "|" | "%")
+        || matches!(name, "MAKEFILE_LIST" | "MAKE_RESTARTS")
+}
+
+fn is_conditional(trimmed: &str) -> bool {
+    let keyword = trimmed.split_whitespace().next().unwrap_or_default();
+    matches!(
+        keyword,
+        "ifeq" | "ifneq" | "ifdef" | "ifndef" | "else" | "endif"
+    )
+}
+
+fn is_directive(trimmed: &str) -> bool {
+    let keyword = trimmed.split_whitespace().next().unwrap_or_default();
+    is_conditional(trimmed)
+        || matches!(
+            keyword,
+            "export" | "unexport" | "override" | "define" | "endef" | "undefine" | "vpath"
+        )
+}
+
 fn is_valid_variable_name(name: &str) -> bool {
     if name.is_empty() {
         return false;
@@ -100,3 +363,298 @@ fn is_valid_variable_name(name: &str) -> bool {
     name.chars()
         .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
 }
+
+pub struct DanglingContinuation;
+
+impl Rule for DanglingContinuation {
+    fn id(&self) -> &'static str {
+        "MK006"
+    }
+
+    fn name(&self) -> &'static str {
+        "Line continuation at end of file"
+    }
+
+    fn description(&self) -> &'static str {
+        "A trailing backslash continues the line onto the next one. On the last line of the \
+         file there is nothing to continue, which usually means a line was deleted or the \
+         file was truncated."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Syntax
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let Some(line_number) = makefile.dangling_continuation else {
+            return Vec::new();
+        };
+        let line = content
+            .lines()
+            .nth(line_number - 1)
+            .unwrap_or_default()
+            .trim_end_matches('\r');
+        let kept = line[..line.len() - 1].trim_end();
+
+        vec![Diagnostic::new(
+            self.id(),
+            Severity::Warning,
+            "Line continuation has no following line to continue",
+            line_number,
+            line.len(),
+        )
+        .with_span(line_number, line.len() + 1)
+        .with_fix(Fix::new("Remove trailing backslash").add_edit(Edit::new(
+            line_number,
+            kept.len() + 1,
+            line_number,
+            line.len() + 1,
+            String::new(),
+        )))]
+    }
+}
+
+pub struct DoubledOperator;
+
+impl Rule for DoubledOperator {
+    fn id(&self) -> &'static str {
+        "MK007"
+    }
+
+    fn name(&self) -> &'static str {
+        "Doubled assignment operator"
+    }
+
+    fn description(&self) -> &'static str {
+        "Make has no `==` or `:==` operator. `FOO == bar` is read as `FOO = = bar`, so the \
+         value silently starts with `=`. Use the single operator that was intended."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Syntax
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, _content: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for variable in &makefile.assignments {
+            let operator = variable.operator.as_str();
+            let extra_column = variable.operator_column + operator.len();
+            // Only an `=` directly after the operator; `FOO = =bar` is deliberate
+            if !variable.value.starts_with('=') || variable.value_start_column != extra_column {
+                continue;
+            }
+
+            // Replace the operator and the whitespace after it, so the value ends up
+            // separated from the operator by a single space
+            let rest = &variable.value[1..];
+            let value = rest.trim_start();
+            let replacement = if value.is_empty() {
+                operator.to_string()
+            } else {
+                format!("{operator} ")
+            };
+
+            diagnostics.push(
+                Diagnostic::new(
+                    self.id(),
+                    Severity::Error,
+                    format!(
+                        "'{operator}=' is not an assignment operator; did you mean '{operator}'?"
+                    ),
+                    variable.line,
+                    variable.operator_column,
+                )
+                .with_span(variable.line, extra_column + 1)
+                .with_fix(
+                    Fix::new(format!("Replace with '{operator}'"))
+                        .as_unsafe()
+                        .add_edit(Edit::new(
+                            variable.line,
+                            variable.operator_column,
+                            variable.line,
+                            extra_column + 1 + rest.len() - value.len(),
+                            replacement,
+                        )),
+                ),
+            );
+        }
+
+        diagnostics
+    }
+}
+
+/// Returns the byte range of the first `$(name)` or `${name}` reference in `value`,
+/// including substitution references such as `$(name:.c=.o)`.
+fn find_self_reference(value: &str, name: &str) -> Option<(usize, usize)> {
+    value.match_indices('$').find_map(|(start, _)| {
+        let close = match value[start + 1..].chars().next()? {
+            '(' => ')',
+            '{' => '}',
+            _ => return None,
+        };
+        let rest = value[start + 2..].strip_prefix(name)?;
+        if !rest.starts_with([close, ':']) {
+            return None;
+        }
+        let end = start + 2 + name.len() + rest.find(close)? + 1;
+        Some((start, end))
+    })
+}
+
+pub struct RecursiveSelfReference;
+
+impl Rule for RecursiveSelfReference {
+    fn id(&self) -> &'static str {
+        "MK008"
+    }
+
+    fn name(&self) -> &'static str {
+        "Recursive variable references itself"
+    }
+
+    fn description(&self) -> &'static str {
+        "A variable assigned with `=` is expanded each time it is used, so `FOO = $(FOO) bar` \
+         makes Make fail with \"Recursive variable 'FOO' references itself\". Use `FOO += bar` \
+         to append, or `:=` to expand the old value once."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Syntax
+    }
+
+    fn fixable(&self) -> bool {
+        true
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut diagnostics = Vec::new();
+
+        for variable in &makefile.assignments {
+            if variable.operator != AssignmentOperator::Recursive {
+                continue;
+            }
+            let Some((start, end)) = find_self_reference(&variable.value, &variable.name) else {
+                continue;
+            };
+
+            let mut diagnostic = Diagnostic::new(
+                self.id(),
+                Severity::Error,
+                format!(
+                    "Recursive variable '{}' references itself; use '+=' or ':='",
+                    variable.name
+                ),
+                variable.line,
+                variable.column,
+            );
+
+            // `FOO = $(FOO) bar` is an append; other self references need a human decision.
+            // The value must also be on the assignment line, not in a define or continuation.
+            let appended = variable.value[end..].trim_start();
+            let value_end = variable.value_start_column + variable.value.len();
+            let on_one_line = lines.get(variable.line - 1).is_some_and(|line| {
+                line.get(variable.value_start_column - 1..value_end - 1) == Some(&variable.value)
+            });
+            if start == 0 && !appended.is_empty() && on_one_line {
+                diagnostic = diagnostic.with_fix(
+                    Fix::new("Append with '+='").as_unsafe().add_edit(Edit::new(
+                        variable.line,
+                        variable.operator_column,
+                        variable.line,
+                        value_end,
+                        format!("+= {appended}"),
+                    )),
+                );
+            }
+            diagnostics.push(diagnostic);
+        }
+
+        diagnostics
+    }
+}
+
+/// Special targets GNU Make gives a meaning to, other than `.PHONY`
+const SPECIAL_TARGETS: &[&str] = &[
+    ".SUFFIXES",
+    ".DEFAULT",
+    ".PRECIOUS",
+    ".INTERMEDIATE",
+    ".NOTINTERMEDIATE",
+    ".SECONDARY",
+    ".SECONDEXPANSION",
+    ".DELETE_ON_ERROR",
+    ".IGNORE",
+    ".LOW_RESOLUTION_TIME",
+    ".SILENT",
+    ".EXPORT_ALL_VARIABLES",
+    ".NOTPARALLEL",
+    ".ONESHELL",
+    ".POSIX",
+    ".WAIT",
+];
+
+pub struct UnknownSpecialTarget;
+
+impl Rule for UnknownSpecialTarget {
+    fn id(&self) -> &'static str {
+        "MK009"
+    }
+
+    fn name(&self) -> &'static str {
+        "Unknown special target"
+    }
+
+    fn description(&self) -> &'static str {
+        "A target like `.PHNOY:` looks like one of Make's special targets but isn't one, so \
+         Make treats it as an ordinary target and the declaration has no effect. This is \
+         usually a misspelling of a special target such as `.PHONY` or `.SECONDARY`."
+    }
+
+    fn category(&self) -> RuleCategory {
+        RuleCategory::Syntax
+    }
+
+    fn check(&self, makefile: &Makefile, content: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut diagnostics = Vec::new();
+
+        for (name, declarations) in &makefile.special_target_lines {
+            if SPECIAL_TARGETS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let suggestion = std::iter::once(".PHONY")
+                .chain(SPECIAL_TARGETS.iter().copied())
+                .map(|known| (levenshtein(name, known), known))
+                .min_by_key(|(distance, _)| *distance)
+                .filter(|(distance, _)| *distance <= 2);
+            let message = match suggestion {
+                Some((_, known)) => {
+                    format!("Unknown special target '{name}'; did you mean '{known}'?")
+                }
+                None => format!("Unknown special target '{name}'"),
+            };
+
+            for &line in declarations {
+                let text = lines.get(line - 1).copied().unwrap_or_default();
+                let column = text.len() - text.trim_start().len() + 1;
+                diagnostics.push(
+                    Diagnostic::new(self.id(), Severity::Warning, message.clone(), line, column)
+                        .with_span(line, column + name.len()),
+                );
+            }
+        }
+
+        diagnostics
+    }
+}
@@ -0,0 +1,1201 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn rumk() -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_rumk"));
+    cmd.env("NO_COLOR", "1");
+    cmd.env(
+        "RUMK_CACHE_DIR",
+        std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("cache"),
+    );
+    cmd
+}
+
+#[test]
+fn test_output_writes_json_to_file() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    let report = dir.path().join("report.json");
+    fs::write(&makefile, "clean:\n\trm -rf build/\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(["--format", "json", "--output"])
+        .arg(&report)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("MK201"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+    let diagnostics = json.as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["rule_id"], "MK201");
+}
+
+#[test]
+fn test_output_file_keeps_terminal_colors() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    let report = dir.path().join("report.txt");
+    fs::write(&makefile, WITH_WARNING).unwrap();
+
+    let output = rumk()
+        .env_remove("NO_COLOR")
+        .env("CLICOLOR_FORCE", "1")
+        .arg("check")
+        .arg(&makefile)
+        .arg("--output")
+        .arg(&report)
+        .output()
+        .unwrap();
+
+    let written = fs::read_to_string(&report).unwrap();
+    assert!(written.contains("[MK201]"), "{written}");
+    assert!(!written.contains('\x1b'), "{written}");
+    // The summary still goes to the terminal, in color
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Found"), "{stdout}");
+    assert!(stdout.contains('\x1b'), "{stdout}");
+}
+
+fn check_status(content: &str, flags: &[&str]) -> i32 {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, content).unwrap();
+
+    rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(flags)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+const WITH_ERROR: &str = ".PHONY: build\nbuild:\n    cc main.c\n";
+const WITH_WARNING: &str = "clean:\n\trm -rf build/\n";
+
+#[test]
+fn test_errors_fail_by_default() {
+    assert_eq!(check_status(WITH_ERROR, &[]), 1);
+    assert_eq!(check_status(WITH_WARNING, &[]), 0);
+}
+
+#[test]
+fn test_exit_zero_ignores_errors() {
+    assert_eq!(check_status(WITH_ERROR, &["--exit-zero"]), 0);
+}
+
+#[test]
+fn test_error_on_warning_fails_on_warnings() {
+    assert_eq!(check_status(WITH_WARNING, &["--error-on-warning"]), 1);
+}
+
+#[test]
+fn test_jsonl_emits_one_object_per_line() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Makefile"), "clean:\n\trm -f app\n").unwrap();
+    fs::write(
+        dir.path().join("rules.mk"),
+        "test:\n\tpytest\ninstall:\n\tcp app /tmp\n",
+    )
+    .unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(dir.path())
+        .args(["--format", "jsonl"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    for line in &lines {
+        assert_eq!(line["rule_id"], "MK201");
+        assert!(line["path"]
+            .as_str()
+            .unwrap()
+            .starts_with(dir.path().to_str().unwrap()));
+    }
+}
+
+#[test]
+fn test_json_version_2_includes_file_paths() {
+    let dir = TempDir::new().unwrap();
+    let first = dir.path().join("Makefile");
+    let second = dir.path().join("rules.mk");
+    fs::write(&first, "clean:\n\trm -f app\n").unwrap();
+    fs::write(&second, "FOO = bar\n\ntest:\n\tpytest\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(dir.path())
+        .args(["--format", "json", "--json-version", "2"])
+        .output()
+        .unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["version"], 2);
+    let diagnostics = json["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+
+    let paths: Vec<&str> = diagnostics
+        .iter()
+        .map(|d| d["path"].as_str().unwrap())
+        .collect();
+    assert!(paths.contains(&first.to_str().unwrap()));
+    assert!(paths.contains(&second.to_str().unwrap()));
+    for diagnostic in diagnostics {
+        let line = diagnostic["line"].as_u64().unwrap();
+        assert_eq!(
+            line,
+            if diagnostic["path"] == first.to_str().unwrap() {
+                1
+            } else {
+                3
+            }
+        );
+    }
+}
+
+#[test]
+fn test_json_includes_byte_offsets() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    let content = "# build\n.PHONY: all\n\nall:\n\t@make -C sub\n";
+    fs::write(&makefile, content).unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnostic = &json.as_array().unwrap()[0];
+    assert_eq!(diagnostic["rule_id"], "MK208");
+    assert_eq!(
+        diagnostic["offset"].as_u64().unwrap() as usize,
+        content.find("make -C").unwrap()
+    );
+}
+
+#[test]
+fn test_watch_rechecks_after_change() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, "clean:\n\trm -f app\n").unwrap();
+
+    let mut child = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .arg("--watch")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    let wait_for = |needle: &str| loop {
+        let line = receiver
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap_or_else(|_| panic!("no output containing {needle:?}"));
+        if line.contains(needle) {
+            break;
+        }
+    };
+
+    wait_for("Target 'clean'");
+    fs::write(&makefile, "test:\n\tpytest\n").unwrap();
+    wait_for("Target 'test'");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_baseline_hides_recorded_findings() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    let baseline = dir.path().join("rumk-baseline.json");
+    fs::write(&makefile, "clean:\n\trm -f app\n").unwrap();
+
+    let run = |extra: &[&str]| {
+        let output = rumk()
+            .arg("check")
+            .arg(&makefile)
+            .args(["--format", "jsonl", "--baseline"])
+            .arg(&baseline)
+            .args(extra)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert!(run(&["--write-baseline"]).is_empty());
+    assert!(baseline.exists());
+
+    // The baselined finding moves down a line, and a new one is introduced
+    fs::write(
+        &makefile,
+        "# Targets\nclean:\n\trm -f app\n\ntest:\n\tpytest\n",
+    )
+    .unwrap();
+    let diagnostics = run(&[]);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["rule_id"], "MK201");
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("'test'"));
+}
+
+#[test]
+fn test_baseline_matches_paths_however_named() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Makefile"), "clean:\n\trm -f app\n").unwrap();
+
+    let run = |makefile: &str, extra: &[&str]| {
+        rumk()
+            .current_dir(dir.path())
+            .args(["check", makefile, "--baseline", "rumk-baseline.json"])
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    assert!(run("Makefile", &["--write-baseline"]).status.success());
+    let output = run("./Makefile", &[]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(run(dir.path().join("Makefile").to_str().unwrap(), &[])
+        .status
+        .success());
+}
+
+#[test]
+fn test_baseline_with_unknown_version_is_rejected() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    let baseline = dir.path().join("rumk-baseline.json");
+    fs::write(&makefile, "clean:\n\trm -f app\n").unwrap();
+    fs::write(&baseline, r#"{"version": 2, "fingerprints": {}}"#).unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .arg("--baseline")
+        .arg(&baseline)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unsupported baseline version 2"));
+}
+
+#[test]
+fn test_statistics_counts_findings_per_rule() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Makefile"), "clean:\n\trm -f app\n").unwrap();
+    fs::write(
+        dir.path().join("rules.mk"),
+        "test:\n\tpytest\ninstall:\n\tmake -C sub\n",
+    )
+    .unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(dir.path())
+        .args(["--format", "jsonl", "--statistics"])
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let emitted: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let count = |rule_id: &str| emitted.iter().filter(|d| d["rule_id"] == rule_id).count();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let rows: Vec<Vec<&str>> = stderr
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][..2], ["3", "MK201"]);
+    assert_eq!(rows[1][..2], ["1", "MK208"]);
+    assert_eq!(count("MK201"), 3);
+    assert_eq!(count("MK208"), 1);
+}
+
+#[test]
+fn test_cache_reuses_results_for_unchanged_files() {
+    let dir = TempDir::new().unwrap();
+    let cache = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, "clean:\n\trm -f app\n\t@make -C sub\n").unwrap();
+
+    let run = |extra: &[&str]| {
+        rumk()
+            .env("RUMK_CACHE_DIR", cache.path())
+            .arg("check")
+            .arg(&makefile)
+            .args(["--format", "json"])
+            .args(extra)
+            .output()
+            .unwrap()
+            .stdout
+    };
+    let entries = || fs::read_dir(cache.path()).map_or(0, |entries| entries.count());
+
+    assert_eq!(run(&["--no-cache"]), run(&["--no-cache"]));
+    assert_eq!(entries(), 0);
+
+    let first = run(&[]);
+    assert_eq!(entries(), 1);
+    let second = run(&[]);
+    assert_eq!(first, second);
+    assert_eq!(entries(), 1);
+
+    let json: serde_json::Value = serde_json::from_slice(&second).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_parse_error_is_reported_as_diagnostic() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, "all:\n\ndefine BANNER\n\techo hi\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let diagnostics = json.as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["rule_id"], "MK000");
+    assert_eq!(diagnostics[0]["severity"], "Error");
+    assert_eq!(diagnostics[0]["line"], 3);
+}
+
+#[test]
+fn test_fix_only_applies_selected_rules() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, ".PHONY: all\nall:\n    make -C sub\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(["--fix", "--fix-only", "MK001"])
+        .output()
+        .unwrap();
+
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[MK208]"));
+    assert_eq!(
+        fs::read_to_string(&makefile).unwrap(),
+        ".PHONY: all\nall:\n\tmake -C sub\n"
+    );
+}
+
+#[test]
+fn test_unsafe_fixes_require_opt_in() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    let content = ".PHONY: all\nall:\n    make -C sub\n";
+    fs::write(&makefile, content).unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .arg("--fix")
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 unsafe fixes available"));
+    assert_eq!(
+        fs::read_to_string(&makefile).unwrap(),
+        ".PHONY: all\nall:\n\tmake -C sub\n"
+    );
+
+    fs::write(&makefile, content).unwrap();
+    rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(["--fix", "--unsafe-fixes"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&makefile).unwrap(),
+        ".PHONY: all\nall:\n\t$(MAKE) -C sub\n"
+    );
+}
+
+#[test]
+fn test_fix_in_directory_reports_fixed_count() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Makefile"), WITH_ERROR).unwrap();
+    fs::write(dir.path().join("rules.mk"), WITH_ERROR).unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(dir.path())
+        .arg("--fix")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Fixed 2 issues"), "{stdout}");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("rules.mk")).unwrap(),
+        ".PHONY: build\nbuild:\n\tcc main.c\n"
+    );
+}
+
+#[test]
+fn test_files_config_adjusts_directory_walk() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("rumk.toml");
+    fs::write(
+        &config,
+        "[files]\ninclude = [\"*.mak\"]\nexclude = [\"vendor/**\"]\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("vendor")).unwrap();
+    fs::write(dir.path().join("vendor/Makefile"), "clean:\n\trm -f app\n").unwrap();
+    fs::write(dir.path().join("build.mak"), "test:\n\tpytest\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "clean:\n\trm -f app\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(dir.path())
+        .args(["--format", "jsonl", "--config"])
+        .arg(&config)
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut paths: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            let diagnostic: serde_json::Value = serde_json::from_str(line).unwrap();
+            diagnostic["path"].as_str().unwrap().to_string()
+        })
+        .collect();
+    paths.dedup();
+    assert_eq!(
+        paths,
+        vec![dir.path().join("build.mak").to_str().unwrap().to_string()]
+    );
+}
+
+#[test]
+fn test_summary_reports_unreadable_files() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Makefile"), ".PHONY: all\nall:\n\ttrue\n").unwrap();
+    fs::write(dir.path().join("broken.mk"), b"X = \xff\n").unwrap();
+
+    let output = rumk().arg("check").arg(dir.path()).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("1 file could not be read"), "{stdout}");
+    assert!(!stdout.contains("checked successfully"), "{stdout}");
+}
+
+#[test]
+fn test_unreadable_file_in_directory_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Makefile"), WITH_WARNING).unwrap();
+    fs::write(dir.path().join("broken.mk"), b"X = \xff\n").unwrap();
+
+    for flags in [&[][..], &["--exit-zero"]] {
+        let output = rumk()
+            .arg("check")
+            .arg(dir.path())
+            .args(flags)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(3), "{flags:?}");
+    }
+}
+
+#[test]
+fn test_timings_lists_every_executed_rule() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, "clean:\n\trm -f app\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .arg("--timings")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let (rules, files) = stderr.split_once("Parse timings:").unwrap();
+    let timed: Vec<&str> = rules
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .filter(|word| word.starts_with("MK"))
+        .collect();
+    for rule in rumk::rules::get_default_rules() {
+        assert!(timed.contains(&rule.id()), "missing {}", rule.id());
+    }
+    assert!(files.contains(makefile.to_str().unwrap()));
+}
+
+#[test]
+fn test_directory_output_is_sorted_by_path() {
+    let dir = TempDir::new().unwrap();
+    for name in [
+        "zeta.mk",
+        "sub/Makefile",
+        "alpha.mk",
+        "sub/beta.mk",
+        "Makefile",
+    ] {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "clean:\n\trm -f app\n").unwrap();
+    }
+
+    let run = || {
+        let output = rumk()
+            .arg("check")
+            .arg(dir.path())
+            .args(["--format", "jsonl"])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let diagnostic: serde_json::Value = serde_json::from_str(line).unwrap();
+                diagnostic["path"].as_str().unwrap().to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let paths = run();
+    let expected: Vec<String> = [
+        "Makefile",
+        "alpha.mk",
+        "sub/Makefile",
+        "sub/beta.mk",
+        "zeta.mk",
+    ]
+    .iter()
+    .map(|name| dir.path().join(name).to_str().unwrap().to_string())
+    .collect();
+    assert_eq!(paths, expected);
+    assert_eq!(run(), paths);
+}
+
+#[test]
+fn test_multiple_paths_share_one_summary() {
+    let dir = TempDir::new().unwrap();
+    let first = dir.path().join("Makefile");
+    let second = dir.path().join("lib.mk");
+    fs::write(&first, "clean:\n\trm -f app\n").unwrap();
+    fs::write(&second, ".PHONY: build\nbuild:\n    cc main.c\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&first)
+        .arg(&second)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("{}:1:1: [MK201]", first.display())));
+    assert!(stdout.contains(&format!("{}:3:1: [MK001]", second.display())));
+    assert_eq!(stdout.matches("Found ").count(), 1);
+    assert!(stdout.contains("Found 2 issues in 2 files (2 files checked)"));
+}
+
+#[test]
+fn test_glob_path_arguments_are_expanded() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    for name in ["a.mk", "b.mk", "sub/c.mk", "notes.txt"] {
+        fs::write(dir.path().join(name), "clean:\n\trm -f app\n").unwrap();
+    }
+
+    let paths = |pattern: &str| {
+        let output = rumk()
+            .current_dir(dir.path())
+            .args(["check", pattern, "--format", "jsonl"])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let diagnostic: serde_json::Value = serde_json::from_str(line).unwrap();
+                diagnostic["path"].as_str().unwrap().to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(paths("*.mk"), vec!["a.mk", "b.mk"]);
+    assert_eq!(paths("**/*.mk"), vec!["a.mk", "b.mk", "sub/c.mk"]);
+
+    let missing = rumk()
+        .current_dir(dir.path())
+        .args(["check", "*.make"])
+        .output()
+        .unwrap();
+    assert!(!missing.status.success());
+}
+
+#[test]
+fn test_gitignored_files_are_skipped_unless_no_ignore() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join(".gitignore"), "vendor/\n").unwrap();
+    fs::create_dir(dir.path().join("vendor")).unwrap();
+    fs::write(dir.path().join("vendor/Makefile"), "clean:\n\trm -f app\n").unwrap();
+    fs::write(dir.path().join("Makefile"), "test:\n\tpytest\n").unwrap();
+
+    let checked = |flags: &[&str]| {
+        let output = rumk()
+            .arg("check")
+            .arg(dir.path())
+            .args(["--format", "jsonl"])
+            .args(flags)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().lines().count()
+    };
+
+    assert_eq!(checked(&[]), 1);
+    assert_eq!(checked(&["--no-ignore"]), 2);
+}
+
+#[test]
+fn test_changed_only_checks_files_modified_since_head() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("app")).unwrap();
+    fs::create_dir(dir.path().join("lib")).unwrap();
+    fs::write(dir.path().join("app/Makefile"), "test:\n\tpytest\n").unwrap();
+    fs::write(dir.path().join("lib/Makefile"), "test:\n\tpytest\n").unwrap();
+
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .current_dir(dir.path())
+            .args(["-c", "user.name=rumk", "-c", "user.email=rumk@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+    fs::write(dir.path().join("app/Makefile"), "test:\n\tpytest -q\n").unwrap();
+
+    let checked = |flags: &[&str]| {
+        let output = rumk()
+            .current_dir(dir.path())
+            .args(["check", ".", "--format", "jsonl"])
+            .args(flags)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(checked(&[]).lines().count(), 2);
+    let changed = checked(&["--changed-only"]);
+    assert_eq!(changed.lines().count(), 1);
+    assert!(changed.contains("app"));
+}
+
+#[test]
+fn test_explain_all_lists_every_rule() {
+    let output = rumk().args(["explain", "--all"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    for rule in rumk::rules::get_all_rules() {
+        assert!(stdout.contains(&format!(
+            "Rule: {}\nCategory: {:?}",
+            rule.id(),
+            rule.category()
+        )));
+    }
+
+    let output = rumk()
+        .args(["explain", "--all", "--markdown"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("# Rules\n"));
+    assert!(stdout.contains("## MK001: "));
+}
+
+#[test]
+fn test_explain_suggests_closest_rule_id() {
+    let output = rumk().args(["explain", "MK10"]).output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("did you mean MK101?"), "{stderr}");
+}
+
+#[test]
+fn test_comment_directives_suppress_findings() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(
+        &makefile,
+        "\
+A B = 1
+# rumk: disable-next-line MK002
+C D = 1
+# rumk: disable MK002
+E F = 1
+# rumk: enable MK002
+G H = 1
+",
+    )
+    .unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(["--format", "jsonl"])
+        .output()
+        .unwrap();
+    let lines: Vec<u64> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<serde_json::Value>(line).unwrap()["line"]
+                .as_u64()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(lines, vec![1, 7]);
+}
+
+#[test]
+fn test_max_issues_limits_output() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, "A B = 1\nC D = 1\nE F = 1\nG H = 1\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .args(["--max-issues", "2"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("[MK002]").count(), 2);
+    assert!(stdout.contains("... and 2 more"));
+    assert!(stdout.contains("Found 4 issues"));
+}
+
+#[test]
+fn test_fail_level_sets_minimum_failing_severity() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("rumk.toml");
+    fs::write(&config, "").unwrap();
+    let config = config.to_str().unwrap();
+    // Only an MK216 info finding, for the misspelled prerequisite
+    const WITH_INFO: &str = ".DEFAULT_GOAL := all\n.PHONY: all\nall: tset\n";
+
+    assert_eq!(check_status(WITH_ERROR, &["--fail-level", "error"]), 1);
+    assert_eq!(check_status(WITH_WARNING, &["--fail-level", "error"]), 0);
+    assert_eq!(check_status(WITH_WARNING, &["--fail-level", "warning"]), 1);
+    assert_eq!(
+        check_status(WITH_INFO, &["-c", config, "--fail-level", "warning"]),
+        0
+    );
+    assert_eq!(
+        check_status(WITH_INFO, &["-c", config, "--fail-level", "info"]),
+        1
+    );
+
+    fs::write(dir.path().join("rumk.toml"), "fail_level = \"info\"\n").unwrap();
+    assert_eq!(check_status(WITH_INFO, &["-c", config]), 1);
+    assert_eq!(
+        check_status(WITH_INFO, &["-c", config, "--fail-level", "error"]),
+        0
+    );
+}
+
+#[test]
+fn test_exit_codes_distinguish_findings_from_errors() {
+    assert_eq!(check_status("all:\n\t@echo ok\n.PHONY: all\n", &[]), 0);
+    assert_eq!(check_status(WITH_ERROR, &[]), 1);
+    assert_eq!(check_status(WITH_ERROR, &["--no-such-flag"]), 2);
+
+    let dir = TempDir::new().unwrap();
+    let missing = rumk()
+        .arg("check")
+        .arg(dir.path().join("Makefile"))
+        .output()
+        .unwrap();
+    assert_eq!(missing.status.code(), Some(3));
+}
+
+#[test]
+fn test_editorconfig_sets_max_line_length() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join(".editorconfig"),
+        "root = true\n\n[*]\nmax_line_length = 200\n\n[{Makefile,*.mk}]\nindent_style = tab\nmax_line_length = 80\n",
+    )
+    .unwrap();
+    let long_line = format!("# {}\n", "x".repeat(88));
+    fs::write(dir.path().join("Makefile"), &long_line).unwrap();
+
+    let check = || {
+        let output = rumk()
+            .current_dir(dir.path())
+            .arg("check")
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(check().contains("Line length 90 exceeds maximum of 80"));
+
+    // An explicit option in the rumk config takes precedence
+    fs::write(
+        dir.path().join("rumk.toml"),
+        "[rules]\n\"MK101\" = { enabled = true, options = { max = 100 } }\n",
+    )
+    .unwrap();
+    assert!(!check().contains("MK101"));
+}
+
+#[test]
+fn test_editorconfig_is_read_for_each_file() {
+    let dir = TempDir::new().unwrap();
+    let project = dir.path().join("project");
+    fs::create_dir_all(project.join("mk")).unwrap();
+    fs::write(
+        project.join(".editorconfig"),
+        "root = true\n\n[*.mk]\nmax_line_length = 60\n\n[Makefile]\nmax_line_length = 80\n",
+    )
+    .unwrap();
+    let long_line = format!("# {}\n", "x".repeat(68));
+    fs::write(project.join("Makefile"), &long_line).unwrap();
+    fs::write(project.join("mk/rules.mk"), &long_line).unwrap();
+
+    // Run from outside the project, so only the files' own directories lead to the settings
+    let output = rumk()
+        .current_dir(dir.path())
+        .args(["check", "project"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("Line length 70 exceeds maximum of 60"),
+        "{stdout}"
+    );
+    assert_eq!(stdout.matches("[MK101]").count(), 1, "{stdout}");
+}
+
+#[test]
+fn test_summary_format_prints_only_totals() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Makefile"), WITH_ERROR).unwrap();
+    fs::write(dir.path().join("clean.mk"), WITH_WARNING).unwrap();
+    fs::write(dir.path().join("ok.mk"), ".PHONY: all\nall:\n\t@echo ok\n").unwrap();
+
+    let output = rumk()
+        .arg("check")
+        .arg(dir.path())
+        .args(["--format", "summary"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(!stdout.contains("[MK"), "{stdout}");
+    assert!(!stdout.contains("No issues found"), "{stdout}");
+    assert_eq!(
+        stdout,
+        "Files checked: 3\nFiles with issues: 2\nIssues: 2 (1 errors, 1 warnings, 0 info)\n"
+    );
+}
+
+#[test]
+fn test_config_check_reports_unknown_rule() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("rumk.toml");
+    fs::write(&config, "[rules]\n\"MK999\" = { enabled = false }\n").unwrap();
+
+    let output = rumk()
+        .args(["check", "--config-check", "--config"])
+        .arg(&config)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown rule id 'MK999'"), "{stderr}");
+
+    fs::write(&config, "[rules]\n\"MK101\" = { enabled = false }\n").unwrap();
+    let output = rumk()
+        .args(["check", "--config-check", "--config"])
+        .arg(&config)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_unknown_rule_id_in_config_warns() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("Makefile"),
+        ".PHONY: all\nall:\n\t@echo ok\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("rumk.toml"),
+        "[rules]\n\"MK10\" = { enabled = false }\n",
+    )
+    .unwrap();
+
+    let output = rumk()
+        .current_dir(dir.path())
+        .arg("check")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown rule id 'MK10'"), "{stderr}");
+}
+
+#[test]
+fn test_fix_rechecks_until_no_fixes_remain() {
+    let dir = TempDir::new().unwrap();
+    let makefile = dir.path().join("Makefile");
+    let config = dir.path().join("rumk.toml");
+    fs::write(&config, "[rules]\n\"MK219\" = { enabled = false }\n").unwrap();
+
+    // Removing the dangling `\` on the last line leaves the previous line dangling
+    let content = "X = a \\\n\\\n";
+    fs::write(&makefile, content).unwrap();
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .arg("--config")
+        .arg(&config)
+        .args(["--fix", "--fix-passes", "1"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[MK006]"));
+    assert_eq!(fs::read_to_string(&makefile).unwrap(), "X = a \\\n\n");
+
+    fs::write(&makefile, content).unwrap();
+    let output = rumk()
+        .arg("check")
+        .arg(&makefile)
+        .arg("--config")
+        .arg(&config)
+        .arg("--fix")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Fixed 2 issues"), "{stdout}");
+    assert!(!stdout.contains("[MK006]"), "{stdout}");
+    assert_eq!(fs::read_to_string(&makefile).unwrap(), "X = a\n\n");
+}
+
+fn check_stdin(content: &str, args: &[&str]) -> std::process::Output {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = rumk()
+        .arg("check")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The process may stop reading early once it's past the limit
+    let _ = child.stdin.take().unwrap().write_all(content.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_is_checked() {
+    let output = check_stdin("all:\n    echo hi\n", &["--stdin"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("-:2:1: [MK001]"), "{stdout}");
+
+    let output = check_stdin(".PHONY: all\nall:\n\t@echo hi\n", &["-"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_stdin_path_with_fix_is_a_usage_error() {
+    for args in [&["-", "--fix"][..], &["--stdin", "--fix"]] {
+        let output = check_stdin(WITH_ERROR, args);
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+    }
+}
+
+#[test]
+fn test_stdin_over_max_input_bytes_fails_cleanly() {
+    let content = "# padding\n".repeat(100);
+    let output = check_stdin(&content, &["--stdin", "--max-input-bytes", "500"]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("exceeds the limit of 500 bytes"),
+        "{stderr}"
+    );
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_rules_json_lists_metadata() {
+    let output = rumk().args(["rules", "--json"]).output().unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let rules = json.as_array().unwrap();
+    assert!(rules.len() > 30);
+
+    for rule in rules {
+        for key in ["id", "name", "description", "category"] {
+            assert!(rule[key].is_string(), "{key} in {rule}");
+        }
+        for key in ["default_enabled", "opt_in", "fixable"] {
+            assert!(rule[key].is_boolean(), "{key} in {rule}");
+        }
+        for option in rule["options"].as_array().unwrap() {
+            assert!(option["name"].is_string());
+            assert!(option["type"].is_string());
+            assert!(option["help"].is_string());
+            assert!(!option["default"].is_null());
+        }
+    }
+
+    let tab = rules.iter().find(|r| r["id"] == "MK001").unwrap();
+    assert_eq!(tab["category"], "syntax");
+    assert_eq!(tab["default_enabled"], true);
+    assert_eq!(tab["fixable"], true);
+
+    let echo = rules.iter().find(|r| r["id"] == "MK109").unwrap();
+    assert_eq!(
+        echo["options"][1],
+        serde_json::json!({
+            "name": "threshold",
+            "type": "float",
+            "default": 0.5,
+            "help": "Share of echo lines that makes a style the majority",
+        })
+    );
+}
+
+#[test]
+fn test_output_config_sets_colors_and_fix_indicator() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("rumk.toml");
+    fs::write(
+        &config,
+        "[output]\nerror_color = \"magenta\"\nfix_indicator = \"(fixable)\"\n",
+    )
+    .unwrap();
+    let makefile = dir.path().join("Makefile");
+    fs::write(&makefile, WITH_ERROR).unwrap();
+
+    let output = rumk()
+        .env_remove("NO_COLOR")
+        .env("CLICOLOR_FORCE", "1")
+        .arg("check")
+        .arg(&makefile)
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\x1b[35m[MK001]\x1b[0m"), "{stdout}");
+    assert!(stdout.contains("(fixable)"));
+    assert!(!stdout.contains("[*]"));
+}
+
+#[test]
+fn test_azure_format_emits_logging_commands() {
+    let output = check_stdin(WITH_WARNING, &["--stdin", "--format", "azure"]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "##vso[task.logissue type=warning;sourcepath=-;linenumber=1;columnnumber=1]\
+         Target 'clean' should be declared .PHONY\n"
+    );
+}
+
+#[test]
+fn test_follow_includes_reports_include_cycles() {
+    let dir = TempDir::new().unwrap();
+    let config = dir.path().join("rumk.toml");
+    fs::write(&config, "[rules]\n\"MK227\" = { enabled = true }\n").unwrap();
+    fs::write(dir.path().join("a.mk"), "include b.mk\n").unwrap();
+    fs::write(dir.path().join("b.mk"), "include a.mk\n").unwrap();
+
+    let run = |flags: &[&str]| {
+        let output = rumk()
+            .arg("check")
+            .arg(dir.path().join("a.mk"))
+            .args(["--format", "jsonl", "--config"])
+            .arg(&config)
+            .args(flags)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(!run(&[]).contains("MK227"));
+    let stdout = run(&["--follow-includes"]);
+    assert!(
+        stdout.contains("Include cycle: a.mk -> b.mk -> a.mk"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use colored::Color;
+    use rumk::config::Config;
+    use rumk::parser::parse;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn load(toml: &str) -> anyhow::Result<Config> {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("rumk.toml");
+        fs::write(&path, toml).unwrap();
+        Config::from_file(&path)
+    }
+
+    #[test]
+    fn test_rule_options_are_applied() {
+        let config = load(
+            r#"
+[rules]
+"MK105" = { enabled = true, options = { prefer = "brace" } }
+"#,
+        )
+        .unwrap();
+
+        let content = "BIN = $(OUT)/app\n";
+        let makefile = parse(content).unwrap();
+        let rule = config.rules.iter().find(|r| r.id() == "MK105").unwrap();
+        assert_eq!(rule.check(&makefile, content).len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_rule_option_is_an_error() {
+        let result = load(
+            r#"
+[rules]
+"MK105" = { enabled = true, options = { prefer = "square" } }
+"#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_override_disables_rule_for_matching_paths() {
+        let config = load(
+            r#"
+[[overrides]]
+path = "generated/*.mk"
+rules = { "MK101" = { enabled = false } }
+"#,
+        )
+        .unwrap();
+
+        let has_mk101 = |path: &str| {
+            config
+                .rules_for(Path::new(path))
+                .iter()
+                .any(|r| r.id() == "MK101")
+        };
+        assert!(!has_mk101("generated/rules.mk"));
+        assert!(!has_mk101("./generated/rules.mk"));
+        assert!(has_mk101("src/rules.mk"));
+        assert!(has_mk101("Makefile"));
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_rules_and_options() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("rumk.toml");
+        fs::write(
+            &path,
+            r#"
+[rules]
+"MK999" = { enabled = false }
+"MK101" = { enabled = true, severity = "fatal", options = { maximum = 80 } }
+"MK105" = { enabled = true, options = { prefer = "parens" } }
+"#,
+        )
+        .unwrap();
+
+        let problems = Config::validate_file(&path).unwrap();
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].contains("invalid severity 'fatal'"));
+        assert!(problems[1].contains("unknown option 'maximum'"));
+        assert!(problems[2].contains("unknown rule id 'MK999'"));
+    }
+
+    #[test]
+    fn test_unknown_rule_id_is_a_warning() {
+        let config = load("[rules]\n\"MK10\" = { enabled = false }\n").unwrap();
+        assert_eq!(config.warnings.len(), 1);
+        assert!(config.warnings[0].contains("unknown rule id 'MK10' (did you mean MK101?)"));
+        assert!(config.rules.iter().any(|r| r.id() == "MK101"));
+
+        let result = load("strict = true\n\n[rules]\n\"MK10\" = { enabled = false }\n");
+        assert!(format!("{:#}", result.err().unwrap()).contains("unknown rule id 'MK10'"));
+    }
+
+    #[test]
+    fn test_opt_in_rules_need_explicit_enabling() {
+        let config = load("[rules]\n").unwrap();
+        assert!(!config.rules.iter().any(|r| r.id() == "MK114"));
+
+        let config = load("[rules]\n\"MK114\" = { enabled = true }\n").unwrap();
+        assert!(config.rules.iter().any(|r| r.id() == "MK114"));
+    }
+
+    #[test]
+    fn test_categories_set_rule_defaults() {
+        let config = load("[categories]\nstyle = false\n").unwrap();
+        let ids: Vec<&str> = config.rules.iter().map(|r| r.id()).collect();
+        assert!(!ids.contains(&"MK101"));
+        assert!(!ids.contains(&"MK102"));
+        assert!(ids.contains(&"MK001"));
+        assert!(ids.contains(&"MK201"));
+
+        let config = load(
+            r#"
+[categories]
+style = false
+
+[rules]
+"MK101" = { enabled = true, options = { max = 100 } }
+"#,
+        )
+        .unwrap();
+        let ids: Vec<&str> = config.rules.iter().map(|r| r.id()).collect();
+        assert!(ids.contains(&"MK101"));
+        assert!(!ids.contains(&"MK102"));
+
+        let config = load("[categories]\nstyle = true\n").unwrap();
+        assert!(config.rules.iter().any(|r| r.id() == "MK114"));
+
+        assert!(load("[categories]\nformatting = false\n").is_err());
+    }
+
+    #[test]
+    fn test_output_section_sets_style() {
+        let config =
+            load("[output]\nwarning_color = \"bright blue\"\nfix_indicator = \"~\"\n").unwrap();
+        assert_eq!(config.output.warning_color, Color::BrightBlue);
+        assert_eq!(config.output.error_color, Color::Red);
+        assert_eq!(config.output.fix_indicator, "~");
+
+        let error = load("[output]\ninfo_color = \"mauve\"\n").err().unwrap();
+        assert!(format!("{error:#}").contains("output.info_color: unknown color 'mauve'"));
+    }
+
+    #[test]
+    fn test_rule_options_reading_other_files_are_detected() {
+        assert!(!load("").unwrap().reads_other_files());
+        assert!(load(
+            r#"
+[rules]
+"MK227" = { enabled = true, options = { follow_includes = true } }
+"#
+        )
+        .unwrap()
+        .reads_other_files());
+        assert!(load(
+            r#"
+[[overrides]]
+path = "sub/*.mk"
+rules = { "MK216" = { enabled = true, options = { check_fs = true } } }
+"#
+        )
+        .unwrap()
+        .reads_other_files());
+
+        let mut config = Config::default();
+        let options =
+            std::collections::HashMap::from([("check_fs".to_string(), toml::Value::Boolean(true))]);
+        config.configure_rule("MK216", &options).unwrap();
+        assert!(config.reads_other_files());
+    }
+}
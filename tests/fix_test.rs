@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use rumk::diagnostic::{Diagnostic, Edit, Fix, Severity};
+    use rumk::fix::apply_fixes;
+
+    fn replace(line: usize, start: usize, end: usize, replacement: &str) -> Diagnostic {
+        Diagnostic::new("MK000", Severity::Warning, "test", line, start)
+            .with_fix(Fix::new("test").add_edit(Edit::new(line, start, line, end, replacement)))
+    }
+
+    #[test]
+    fn test_apply_fixes_skips_overlapping_edits() {
+        let content = "FOO = abcdef";
+        let diagnostics = vec![replace(1, 7, 10, "XYZ"), replace(1, 8, 12, "12345")];
+
+        let result = apply_fixes(content, &diagnostics, false);
+
+        assert_eq!(result.content, "FOO = a12345f");
+        assert_eq!(result.applied, 1);
+        assert_eq!(result.skipped, 1);
+    }
+
+    #[test]
+    fn test_apply_fixes_applies_disjoint_edits() {
+        let content = "FOO = abcdef";
+        let diagnostics = vec![replace(1, 7, 8, "A"), replace(1, 12, 13, "F")];
+
+        let result = apply_fixes(content, &diagnostics, false);
+
+        assert_eq!(result.content, "FOO = AbcdeF");
+        assert_eq!(result.applied, 2);
+        assert_eq!(result.skipped, 0);
+    }
+
+    #[test]
+    fn test_apply_fixes_preserves_trailing_newline() {
+        let content = "FOO = abc\nBAR = def\n";
+        let diagnostics = vec![replace(1, 7, 8, "A")];
+
+        let result = apply_fixes(content, &diagnostics, false);
+
+        assert_eq!(result.content, "FOO = Abc\nBAR = def\n");
+    }
+
+    #[test]
+    fn test_apply_fixes_preserves_crlf_line_endings() {
+        let content = "FOO = abc\r\nBAR = def\r\n";
+        let diagnostics = vec![replace(2, 7, 8, "D")];
+
+        let result = apply_fixes(content, &diagnostics, false);
+
+        assert_eq!(result.content, "FOO = abc\r\nBAR = Def\r\n");
+    }
+
+    #[test]
+    fn test_apply_fixes_skips_unsafe_fixes_by_default() {
+        let content = "FOO = abc";
+        let unsafe_fix = Diagnostic::new("MK000", Severity::Warning, "test", 1, 9).with_fix(
+            Fix::new("test")
+                .as_unsafe()
+                .add_edit(Edit::new(1, 9, 1, 10, "C")),
+        );
+        let diagnostics = vec![replace(1, 7, 8, "A"), unsafe_fix];
+
+        let result = apply_fixes(content, &diagnostics, false);
+        assert_eq!(result.content, "FOO = Abc");
+        assert_eq!(result.applied, 1);
+        assert_eq!(result.unsafe_skipped, 1);
+
+        let result = apply_fixes(content, &diagnostics, true);
+        assert_eq!(result.content, "FOO = AbC");
+        assert_eq!(result.applied, 2);
+        assert_eq!(result.unsafe_skipped, 0);
+    }
+}
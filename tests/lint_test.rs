@@ -0,0 +1,161 @@
+#[cfg(test)]
+mod tests {
+    use rumk::config::Config;
+    use rumk::diagnostic::{Diagnostic, Severity};
+    use rumk::lint::{dedup, lint, lint_and_fix, lint_path};
+    use rumk::rules::best_practices::{ExportUndefined, MissingIncludeFile};
+    use rumk::rules::Rule;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lint_returns_sorted_diagnostics() {
+        let content = "all:\n    echo hi\n\nclean:\n    rm -f out\n";
+        let diagnostics = lint(content, &Config::default());
+
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.rule_id.as_str(), d.line))
+            .collect();
+        assert_eq!(
+            found,
+            [("MK201", 1), ("MK001", 2), ("MK201", 4), ("MK001", 5)]
+        );
+        assert_eq!(diagnostics[1].offset, Some(5));
+    }
+
+    #[test]
+    fn test_lint_reports_parse_errors() {
+        let diagnostics = lint("define BANNER\n\techo hi\n", &Config::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule_id, "MK000");
+    }
+
+    #[test]
+    fn test_lint_and_fix_returns_fixed_content() {
+        let config = Config::default();
+
+        let (fixed, remaining) = lint_and_fix(".PHONY: all\nall:\n    echo hi\n", &config);
+        assert_eq!(fixed, ".PHONY: all\nall:\n\techo hi\n");
+        assert!(remaining.is_empty());
+
+        // Unsafe fixes are left to the caller, and line endings are kept
+        let (fixed, remaining) = lint_and_fix(".PHONY: all\r\nall:\r\n    make -C sub", &config);
+        assert_eq!(fixed, ".PHONY: all\r\nall:\r\n\tmake -C sub");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].rule_id, "MK208");
+    }
+
+    #[test]
+    fn test_dedup_removes_identical_diagnostics() {
+        let duplicate = || Diagnostic::new("MK202", Severity::Warning, "Hardcoded path", 3, 5);
+        let mut diagnostics = vec![
+            duplicate(),
+            Diagnostic::new("MK202", Severity::Warning, "Other message", 3, 5),
+            duplicate(),
+        ];
+        dedup(&mut diagnostics);
+
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["Hardcoded path", "Other message"]);
+    }
+
+    fn follow_config(rules: Vec<Box<dyn Rule>>) -> Config {
+        Config {
+            rules,
+            follow_includes: true,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_follow_includes_merges_included_definitions() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("mk")).unwrap();
+        fs::write(dir.path().join("mk/vars.mk"), "TOKEN = secret\n").unwrap();
+        let path = dir.path().join("Makefile");
+        let content = "MK_DIR = mk\ninclude $(MK_DIR)/vars.mk\nexport TOKEN\n";
+        fs::write(&path, content).unwrap();
+
+        let config = follow_config(vec![Box::new(ExportUndefined)]);
+        assert!(lint_path(&path, content, &config).is_empty());
+
+        let config = Config {
+            follow_includes: false,
+            ..follow_config(vec![Box::new(ExportUndefined)])
+        };
+        let diagnostics = lint_path(&path, content, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule_id, "MK218");
+        assert_eq!(diagnostics[0].line, 3);
+    }
+
+    #[test]
+    fn test_follow_includes_reports_missing_includes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Makefile");
+        let content = "include config.mk\n-include local.mk\ninclude deps.mk\n\ndeps.mk:\n\t./gen-deps > $@\n";
+        fs::write(&path, content).unwrap();
+
+        let diagnostics = lint_path(
+            &path,
+            content,
+            &follow_config(vec![Box::new(MissingIncludeFile)]),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Included file 'config.mk' does not exist"
+        );
+
+        let config = Config {
+            follow_includes: false,
+            ..follow_config(vec![Box::new(MissingIncludeFile)])
+        };
+        assert!(lint_path(&path, content, &config).is_empty());
+    }
+
+    #[test]
+    fn test_follow_includes_resolves_nested_paths_from_root() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("mk")).unwrap();
+        // Make runs in the root's directory, so `mk/vars.mk` names a path from there
+        fs::write(dir.path().join("mk/common.mk"), "include mk/vars.mk\n").unwrap();
+        fs::write(dir.path().join("mk/vars.mk"), "TOKEN = secret\n").unwrap();
+        let path = dir.path().join("Makefile");
+        let content = "include mk/common.mk\nexport TOKEN\n";
+        fs::write(&path, content).unwrap();
+
+        let config = follow_config(vec![
+            Box::new(ExportUndefined),
+            Box::new(MissingIncludeFile),
+        ]);
+        assert!(lint_path(&path, content, &config).is_empty());
+    }
+
+    #[test]
+    fn test_follow_includes_reports_missing_nested_includes() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("common.mk"),
+            "include local.mk\n-include optional.mk\n",
+        )
+        .unwrap();
+        let path = dir.path().join("Makefile");
+        let content = "all:\n\t@true\n\ninclude common.mk\n";
+        fs::write(&path, content).unwrap();
+
+        let diagnostics = lint_path(
+            &path,
+            content,
+            &follow_config(vec![Box::new(MissingIncludeFile)]),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 4);
+        assert_eq!(
+            diagnostics[0].message,
+            "File 'local.mk' included from 'common.mk' does not exist"
+        );
+    }
+}
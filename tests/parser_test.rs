@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use rumk::parser::parse;
+    use rumk::parser::{parse, AssignmentOperator, ReferenceKind};
 
     #[test]
     fn test_parse_simple_rule() {
@@ -32,4 +32,333 @@ target: dependency
         assert!(makefile.phonies.contains(&"clean".to_string()));
         assert!(makefile.phonies.contains(&"test".to_string()));
     }
+
+    #[test]
+    fn test_parse_rule_with_inline_recipe() {
+        let content = "help: ; @echo \"$$HELP_TEXT\"\nall: ; echo a=b\n\techo done\n";
+        let makefile = parse(content).unwrap();
+        assert_eq!(makefile.rules.len(), 2);
+
+        let help = &makefile.rules[0];
+        assert!(help.prerequisites.is_empty());
+        assert_eq!(help.recipes.len(), 1);
+        assert_eq!(help.recipes[0].command, "echo \"$$HELP_TEXT\"");
+        assert_eq!(help.recipes[0].line, 1);
+        assert_eq!(help.recipes[0].indentation, "help: ; ");
+        assert!(help.recipes[0].inline);
+        assert!(help.recipes[0].silent);
+
+        let all = &makefile.rules[1];
+        assert_eq!(all.targets, vec!["all"]);
+        assert!(all.prerequisites.is_empty());
+        let commands: Vec<&str> = all.recipes.iter().map(|r| r.command.as_str()).collect();
+        assert_eq!(commands, ["echo a=b", "echo done"]);
+        assert!(!all.recipes[1].inline);
+    }
+
+    #[test]
+    fn test_parse_phony_joins_continuation_lines() {
+        let content = "\n.PHONY: all \\\n\tclean \\\n  test\n\nall:\n\t@true\n";
+        let makefile = parse(content).unwrap();
+        assert_eq!(makefile.phonies, vec!["all", "clean", "test"]);
+        assert_eq!(makefile.phony_lines, vec![2]);
+        // The continued names aren't recipe lines of a rule
+        assert_eq!(makefile.rules.len(), 1);
+        assert_eq!(makefile.rules[0].line, 6);
+    }
+
+    #[test]
+    fn test_parse_rule_line_is_target_line() {
+        let content = "build:\n\tcc a.c\n\tcc b.c\n\nclean:\n\trm -f app\n";
+        let makefile = parse(content).unwrap();
+        assert_eq!(makefile.rules[0].line, 1);
+        assert_eq!(makefile.rules[1].line, 5);
+    }
+
+    #[test]
+    fn test_parse_prerequisites() {
+        let content = "app: main.o util.o | build\nlog:: a\n";
+        let makefile = parse(content).unwrap();
+        assert_eq!(makefile.rules[0].prerequisites, vec!["main.o", "util.o"]);
+        assert_eq!(makefile.rules[0].order_only_prerequisites, vec!["build"]);
+        assert!(!makefile.rules[0].double_colon);
+        assert!(makefile.rules[1].double_colon);
+        assert_eq!(makefile.rules[1].prerequisites, vec!["a"]);
+    }
+
+    #[test]
+    fn test_parse_includes() {
+        let content = "include common.mk rules.mk\n-include local.mk\nincluded: x\n";
+        let makefile = parse(content).unwrap();
+        assert_eq!(makefile.includes.len(), 2);
+        assert_eq!(makefile.includes[0].paths, vec!["common.mk", "rules.mk"]);
+        assert!(!makefile.includes[0].optional);
+        assert_eq!(makefile.includes[1].paths, vec!["local.mk"]);
+        assert!(makefile.includes[1].optional);
+        assert_eq!(makefile.rules.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let content = "# Build settings\nCC = gcc\n  # indented\n";
+        let makefile = parse(content).unwrap();
+        assert_eq!(makefile.comments.len(), 2);
+        assert_eq!(makefile.comments[0].text, " Build settings");
+        assert_eq!(makefile.comments[0].line, 1);
+        assert_eq!(makefile.comments[1].column, 3);
+    }
+
+    #[test]
+    fn test_parse_assignment_operators() {
+        let content = "A = 1\nB := 2\nC ?= 3\nD += 4\nE != echo 5\nF ::= 6\n";
+        let makefile = parse(content).unwrap();
+        assert_eq!(makefile.variables.len(), 6);
+        assert!(makefile.rules.is_empty());
+        assert_eq!(makefile.variables["B"].value, "2");
+        assert_eq!(makefile.variables["B"].operator, AssignmentOperator::Simple);
+        assert_eq!(makefile.variables["B"].operator_column, 3);
+        assert_eq!(
+            makefile.variables["F"].operator,
+            AssignmentOperator::PosixSimple
+        );
+        assert_eq!(makefile.variables["E"].value, "echo 5");
+    }
+
+    #[test]
+    fn test_parse_target_specific_variable_is_rule() {
+        let content = "debug: CFLAGS += -g\n";
+        let makefile = parse(content).unwrap();
+        assert!(makefile.variables.is_empty());
+        assert_eq!(makefile.rules[0].targets, vec!["debug"]);
+        assert!(makefile.rules[0].prerequisites.is_empty());
+    }
+
+    #[test]
+    fn test_parse_continued_recipe() {
+        let content = "app:\n\tgcc a.c \\\n\t  b.c -o app\n\tstrip app\n";
+        let makefile = parse(content).unwrap();
+        let recipes = &makefile.rules[0].recipes;
+        assert_eq!(recipes.len(), 2);
+        assert_eq!(recipes[0].command, "gcc a.c b.c -o app");
+        assert_eq!((recipes[0].line, recipes[0].end_line), (2, 3));
+        assert_eq!((recipes[1].line, recipes[1].end_line), (4, 4));
+    }
+
+    #[test]
+    fn test_parse_default_goal() {
+        let makefile = parse(".DEFAULT_GOAL := build\n").unwrap();
+        assert_eq!(makefile.default_goal.as_deref(), Some("build"));
+        assert!(makefile.variables.is_empty());
+
+        let makefile = parse("all:\n").unwrap();
+        assert_eq!(makefile.default_goal, None);
+    }
+
+    #[test]
+    fn test_parse_vpath_directive() {
+        let makefile = parse("vpath %.h include\nvpath %.c src:lib\nvpath %.o\n").unwrap();
+        assert_eq!(makefile.vpaths.len(), 2);
+        assert_eq!(makefile.vpaths[0].pattern.as_deref(), Some("%.h"));
+        assert_eq!(makefile.vpaths[0].directories, vec!["include"]);
+        assert_eq!(makefile.vpaths[1].directories, vec!["src", "lib"]);
+        assert_eq!(makefile.vpaths[1].line, 2);
+        assert!(makefile.rules.is_empty());
+    }
+
+    #[test]
+    fn test_parse_vpath_variable() {
+        let makefile = parse("VPATH = a:b\n").unwrap();
+        assert_eq!(makefile.vpaths.len(), 1);
+        assert_eq!(makefile.vpaths[0].pattern, None);
+        assert_eq!(makefile.vpaths[0].directories, vec!["a", "b"]);
+        assert_eq!(makefile.variables["VPATH"].value, "a:b");
+    }
+
+    #[test]
+    fn test_parse_define() {
+        let content = "define COMPILE =\n\t$(CC) -c $<\n\t@echo done\nendef\n\nall:\n";
+        let makefile = parse(content).unwrap();
+        let variable = &makefile.variables["COMPILE"];
+        assert_eq!(variable.value, "\t$(CC) -c $<\n\t@echo done");
+        assert_eq!(variable.line, 1);
+        assert_eq!(makefile.rules.len(), 1);
+        assert_eq!(makefile.rules[0].line, 6);
+    }
+
+    #[test]
+    fn test_parse_define_without_endef_fails() {
+        let error = parse("all:\n\ndefine BANNER\n\techo hi\n").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("endef"));
+    }
+
+    #[test]
+    fn test_parse_recipe_prefixes_in_any_order() {
+        let makefile = parse("all:\n\t-@echo a\n\t@-echo b\n\t+$(MAKE) -C sub\n").unwrap();
+        let recipes = &makefile.rules[0].recipes;
+
+        assert_eq!(recipes[0].command, "echo a");
+        assert_eq!(recipes[0].prefix, "-@");
+        assert!(recipes[0].silent && recipes[0].ignore_error && !recipes[0].always_run);
+
+        assert_eq!(recipes[1].command, "echo b");
+        assert_eq!(recipes[1].prefix, "@-");
+        assert!(recipes[1].silent && recipes[1].ignore_error && !recipes[1].always_run);
+
+        assert_eq!(recipes[2].command, "$(MAKE) -C sub");
+        assert_eq!(recipes[2].prefix, "+");
+        assert!(!recipes[2].silent && !recipes[2].ignore_error && recipes[2].always_run);
+    }
+
+    #[test]
+    fn test_parse_export_directives() {
+        let makefile =
+            parse("export CC = gcc\noverride export CFLAGS := -O2\nexport PATH LANG\n").unwrap();
+        assert_eq!(makefile.variables["CC"].column, 8);
+        assert!(makefile.variables.contains_key("CFLAGS"));
+        assert!(!makefile.variables.contains_key("export CC"));
+
+        let exports: Vec<(&str, usize, usize)> = makefile
+            .exports
+            .iter()
+            .map(|export| (export.name.as_str(), export.line, export.column))
+            .collect();
+        assert_eq!(
+            exports,
+            vec![
+                ("CC", 1, 8),
+                ("CFLAGS", 2, 17),
+                ("PATH", 3, 8),
+                ("LANG", 3, 13)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_variable_value_start_column() {
+        let makefile = parse("CC  :=   gcc\nexport EMPTY =\n").unwrap();
+        assert_eq!(makefile.variables["CC"].value_start_column, 10);
+        assert_eq!(makefile.variables["EMPTY"].value_start_column, 15);
+    }
+
+    #[test]
+    fn test_parse_suppression_directives() {
+        let content = "\
+# rumk: disable-next-line MK001, MK101
+all:
+# rumk: disable MK202
+# rumk: disable MK202
+X = /opt
+# rumk: enable MK202
+# rumk: enable MK999
+# rumk: disable
+";
+        let makefile = parse(content).unwrap();
+
+        assert!(makefile.is_suppressed("MK001", 2));
+        assert!(makefile.is_suppressed("MK101", 2));
+        assert!(!makefile.is_suppressed("MK001", 3));
+        assert!(makefile.is_suppressed("MK202", 5));
+        assert!(!makefile.is_suppressed("MK202", 7));
+        assert!(makefile.is_suppressed("MK202", 8));
+        assert!(makefile.is_suppressed("MK105", 100));
+    }
+
+    #[test]
+    fn test_parse_blank_lines_inside_rule() {
+        let makefile = parse("build:\n\n\tcc -c a.c\n\n\n\tcc -c b.c\n\nall:\n").unwrap();
+        assert_eq!(makefile.rules[0].blank_lines, vec![2, 4, 5]);
+        assert!(makefile.rules[1].blank_lines.is_empty());
+    }
+
+    #[test]
+    fn test_parse_references() {
+        let content = "# $(IGNORED)\nDIRS = $(dir $(FOO)) ${SRCS:.c=.o}\nall:\n\t$(CC) -o $@ $(<F) $$HOME $x $(call build,1)\n";
+        let makefile = parse(content).unwrap();
+        let references: Vec<(&str, ReferenceKind, usize, usize)> = makefile
+            .references
+            .iter()
+            .map(|r| (r.name.as_str(), r.kind, r.line, r.column))
+            .collect();
+
+        assert_eq!(
+            references,
+            vec![
+                ("dir", ReferenceKind::Function, 2, 8),
+                ("FOO", ReferenceKind::Variable, 2, 14),
+                ("SRCS", ReferenceKind::Variable, 2, 22),
+                ("CC", ReferenceKind::Variable, 4, 2),
+                ("@", ReferenceKind::Automatic, 4, 11),
+                ("<F", ReferenceKind::Automatic, 4, 14),
+                ("x", ReferenceKind::Variable, 4, 27),
+                ("call", ReferenceKind::Function, 4, 30),
+                ("build", ReferenceKind::Variable, 4, 37),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rule_doc_comments() {
+        let content = "\
+## build the app
+## from source
+build:
+\t@cargo build
+
+# plain comment
+test:
+\t@cargo test
+
+## not attached
+
+clean:
+\t@rm -rf target
+";
+        let makefile = parse(content).unwrap();
+        assert_eq!(
+            makefile.rules[0].doc.as_deref(),
+            Some("build the app\nfrom source")
+        );
+        assert_eq!(makefile.rules[1].doc, None);
+        assert_eq!(makefile.rules[2].doc, None);
+        // Plain comments are still recorded as comments
+        assert_eq!(makefile.comments.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_special_targets() {
+        let content = ".SECONDARY: gen.c gen.h\n.SECONDARY: out.o\n.SUFFIXES:\n.DELETE_ON_ERROR := 1\n.c.o:\n\t$(CC) -c $<\n";
+        let makefile = parse(content).unwrap();
+
+        assert_eq!(
+            makefile.special_targets[".SECONDARY"],
+            ["gen.c", "gen.h", "out.o"]
+        );
+        assert_eq!(makefile.special_target_lines[".SECONDARY"], [1, 2]);
+        assert!(makefile.special_targets[".SUFFIXES"].is_empty());
+        assert_eq!(makefile.special_targets.len(), 2);
+        assert!(makefile.phonies.is_empty());
+    }
+
+    #[test]
+    fn test_parse_keeps_every_assignment() {
+        let content = "CC = gcc\nCFLAGS := -O2\nCC = clang\nCFLAGS += -g\n";
+        let makefile = parse(content).unwrap();
+
+        let lines: Vec<(&str, usize)> = makefile
+            .assignments
+            .iter()
+            .map(|variable| (variable.name.as_str(), variable.line))
+            .collect();
+        assert_eq!(lines, [("CC", 1), ("CFLAGS", 2), ("CC", 3), ("CFLAGS", 4)]);
+
+        let values: Vec<&str> = makefile
+            .assignments_of("CC")
+            .map(|variable| variable.value.as_str())
+            .collect();
+        assert_eq!(values, ["gcc", "clang"]);
+        // The map keeps the last assignment of each name
+        assert_eq!(makefile.variables["CC"].value, "clang");
+        assert_eq!(makefile.variables.len(), 2);
+    }
 }
@@ -0,0 +1,1113 @@
+#[cfg(test)]
+mod tests {
+    use rumk::diagnostic::{Diagnostic, Severity};
+    use rumk::fix::apply_fixes;
+    use rumk::parser::parse;
+    use rumk::rules::best_practices::{
+        BacktickSubstitution, Bashism, DangerousRm, EagerShell, EmptyRecipe, ExportUndefined,
+        GnuFunction, HardcodedPath, IgnoredError, ImplicitDefaultGoal, IncludeCycle,
+        MisplacedDependency, MissingOutputDirectory, MissingPhony, PermissiveChmod, RecursiveMake,
+        RequiredInclude, ShellWithoutFlags, Sudo, ToolVariableOverride, UndefinedPhony,
+        UndefinedPrerequisite, UnusedVariable,
+    };
+    use rumk::rules::style::{
+        AssignmentSpacing, AssignmentSpacingStyle, BlankLineInRecipe, DuplicatePrerequisite,
+        EchoPrefixConsistency, EchoProgress, EmbeddedTab, LineLength, LongPrerequisiteList,
+        LongRecipeCommand, MissingFinalNewline, MultiplePhony, NamingStyle, ReferenceStyle,
+        ShadowedVariable, SilentTarget, TargetNaming, TodoComment, UndocumentedPhony,
+        VariableNaming, VariableReferenceStyle,
+    };
+    use rumk::rules::syntax::{
+        AutomaticVariableAssignment, DanglingContinuation, DoubledOperator, InvalidVariableSyntax,
+        RecursiveSelfReference, TabOutsideRecipe, UnknownSpecialTarget,
+    };
+    use rumk::rules::{OptionKind, Rule};
+    use std::collections::HashMap;
+
+    fn check_with(rule: &dyn Rule, content: &str) -> Vec<Diagnostic> {
+        let makefile = parse(content).unwrap();
+        rule.check(&makefile, content)
+    }
+
+    fn fix_with(rule: &dyn Rule, content: &str) -> String {
+        let makefile = parse(content).unwrap();
+        let diagnostics = rule.check(&makefile, content);
+        apply_fixes(content, &diagnostics, true).content
+    }
+
+    #[test]
+    fn test_missing_phony_fix_appends_to_existing_declaration() {
+        let content = ".PHONY: build\n\nbuild:\n\tcc main.c\n\nclean:\n\trm -f app\n";
+
+        assert_eq!(
+            fix_with(&MissingPhony, content),
+            ".PHONY: build clean\n\nbuild:\n\tcc main.c\n\nclean:\n\trm -f app\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_phony_fix_appends_to_continued_declaration() {
+        let content = ".PHONY: all \\\n\tclean\n\nall:\n\t@true\n\nclean:\n\trm -f app\n\nbuild:\n\tcc main.c\n";
+
+        assert_eq!(
+            fix_with(&MissingPhony, content),
+            ".PHONY: all \\\n\tclean build\n\nall:\n\t@true\n\nclean:\n\trm -f app\n\nbuild:\n\tcc main.c\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_phony_fix_inserts_declaration() {
+        let content = "clean:\n\trm -f app\n";
+
+        assert_eq!(
+            fix_with(&MissingPhony, content),
+            ".PHONY: clean\nclean:\n\trm -f app\n"
+        );
+    }
+
+    #[test]
+    fn test_dangerous_rm_flags_unquoted_variable() {
+        for command in ["rm -rf $(BUILD_DIR)/", "rm -rf ${OUT}", "rm -r -f $$TMPDIR"] {
+            let content = format!("clean:\n\t{command}\n");
+            let diagnostics = check_with(&DangerousRm, &content);
+            assert_eq!(diagnostics.len(), 1, "{command}");
+            assert_eq!(diagnostics[0].rule_id, "MK207");
+        }
+    }
+
+    #[test]
+    fn test_dangerous_rm_ignores_literal_paths() {
+        for command in [
+            "rm -rf build/",
+            "rm -f $(TARGET)",
+            "rm -rf \"$(BUILD_DIR)/\"",
+        ] {
+            let content = format!("clean:\n\t{command}\n");
+            assert!(check_with(&DangerousRm, &content).is_empty(), "{command}");
+        }
+    }
+
+    #[test]
+    fn test_recursive_make_fixes_plain_make() {
+        let content = "sub:\n\t@make -C sub\n";
+        let diagnostics = check_with(&RecursiveMake, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].column, 3);
+        assert_eq!(
+            fix_with(&RecursiveMake, content),
+            "sub:\n\t@$(MAKE) -C sub\n"
+        );
+    }
+
+    #[test]
+    fn test_recursive_make_ignores_make_variable_and_cmake() {
+        let content = "sub:\n\t$(MAKE) -C sub\n\tcmake .\n\tmakedepend main.c\n";
+        assert!(check_with(&RecursiveMake, content).is_empty());
+    }
+
+    #[test]
+    fn test_reference_style_flags_only_non_preferred_form() {
+        let content = "OBJ = $(SRC:.c=.o)\nBIN = ${OUT}/app\n\napp:\n\tcc $(CFLAGS) -o ${BIN}\n";
+
+        let diagnostics = check_with(&VariableReferenceStyle::new(ReferenceStyle::Paren), content);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.message.contains("${")));
+
+        let diagnostics = check_with(&VariableReferenceStyle::new(ReferenceStyle::Brace), content);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.message.contains("$(")));
+    }
+
+    #[test]
+    fn test_reference_style_fix_rewrites_delimiters() {
+        let content = "BIN = ${OUT}/app $$HOME\n";
+        assert_eq!(
+            fix_with(&VariableReferenceStyle::new(ReferenceStyle::Paren), content),
+            "BIN = $(OUT)/app $$HOME\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_recipe_flags_rule_without_commands() {
+        let diagnostics = check_with(&EmptyRecipe::new(), "foo: bar\n\nbaz:\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule_id, "MK209");
+        assert_eq!(diagnostics[0].line, 1);
+    }
+
+    #[test]
+    fn test_empty_recipe_ignores_rules_with_recipe_phony_or_double_colon() {
+        let content = ".PHONY: all\nall: app\nfoo: bar\n\tcp bar foo\nlog:: a\n";
+        assert!(check_with(&EmptyRecipe::new(), content).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_prerequisite_is_reported_and_fixed() {
+        let content = "app: a.o b.o a.o | build build # link\n\tcc -o $@ $^\n";
+
+        let diagnostics = check_with(&DuplicatePrerequisite, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].message.contains("'a.o'"));
+        assert!(diagnostics[1].message.contains("'build'"));
+
+        assert_eq!(
+            fix_with(&DuplicatePrerequisite, content),
+            "app: a.o b.o | build # link\n\tcc -o $@ $^\n"
+        );
+    }
+
+    #[test]
+    fn test_undefined_phony_flags_dangling_name() {
+        let content = "all: app\n\n.PHONY: all tset\n";
+        let diagnostics = check_with(&UndefinedPhony::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("'tset'"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, 13));
+    }
+
+    #[test]
+    fn test_undefined_phony_follows_continuation_lines() {
+        let content = ".PHONY: all \\\n\tclena\n\nall:\n\t@true\n\nclean:\n\trm -f app\n";
+        let diagnostics = check_with(&UndefinedPhony::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Phony target 'clena' is never defined"
+        );
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 2));
+
+        let content = ".PHONY: all \\\n\tclean\n\nall:\n\t@true\n\nclean:\n\trm -f app\n";
+        assert!(check_with(&UndefinedPhony::new(), content).is_empty());
+        assert!(check_with(&MissingPhony, content).is_empty());
+    }
+
+    #[test]
+    fn test_undefined_phony_skips_files_with_includes() {
+        let content = "include common.mk\n.PHONY: lint\n";
+        assert!(check_with(&UndefinedPhony::new(), content).is_empty());
+    }
+
+    #[test]
+    fn test_todo_comment_reports_marker() {
+        let diagnostics = check_with(&TodoComment::new(), "# TODO: fix this\nall:\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "TODO marker: TODO: fix this");
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 3));
+    }
+
+    #[test]
+    fn test_todo_comment_requires_word_boundary() {
+        let content = "# methodology\n# todo lowercase\n# TODOS\n";
+        assert!(check_with(&TodoComment::new(), content).is_empty());
+    }
+
+    #[test]
+    fn test_assignment_spacing_spaced_mode() {
+        let rule = AssignmentSpacingStyle::new(AssignmentSpacing::Spaced);
+        let content = "CC=gcc\nCFLAGS := -O2\nLIBS+=-lm\nEMPTY =\n";
+
+        let diagnostics = check_with(&rule, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            fix_with(&rule, content),
+            "CC = gcc\nCFLAGS := -O2\nLIBS += -lm\nEMPTY =\n"
+        );
+    }
+
+    #[test]
+    fn test_assignment_spacing_tight_mode() {
+        let rule = AssignmentSpacingStyle::new(AssignmentSpacing::Tight);
+        let content = "CC=gcc\nCFLAGS  ?=  -O2\nPREFIX ::= /opt\n";
+
+        let diagnostics = check_with(&rule, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            fix_with(&rule, content),
+            "CC=gcc\nCFLAGS?=-O2\nPREFIX::=/opt\n"
+        );
+    }
+
+    #[test]
+    fn test_backtick_substitution_is_rewritten() {
+        let content = "stamp:\n\tFOO=`date`; echo $$FOO\n";
+        assert_eq!(check_with(&BacktickSubstitution, content).len(), 1);
+        assert_eq!(
+            fix_with(&BacktickSubstitution, content),
+            "stamp:\n\tFOO=$$(date); echo $$FOO\n"
+        );
+    }
+
+    #[test]
+    fn test_backtick_in_single_quotes_is_ignored() {
+        let content = "doc:\n\techo 'run `make` first'\n";
+        assert!(check_with(&BacktickSubstitution, content).is_empty());
+    }
+
+    #[test]
+    fn test_line_length_reports_span_to_end_of_line() {
+        let diagnostics = check_with(&LineLength::new(10), "FOO = 0123456789\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].column, 11);
+        assert_eq!(diagnostics[0].end_line, Some(1));
+        assert_eq!(diagnostics[0].end_column, Some(17));
+    }
+
+    #[test]
+    fn test_line_length_counts_display_width() {
+        // 6 characters in 18 bytes, 12 columns wide
+        let content = "X = 漢字漢字漢字\n";
+        let diagnostics = check_with(&LineLength::new(12), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Line length 16 exceeds maximum of 12"
+        );
+        // The first character past the limit is the fifth ideograph, at display column 13
+        // and byte 17
+        assert_eq!(diagnostics[0].column, 13);
+        assert_eq!(diagnostics[0].end_column, Some(17));
+        assert_eq!(diagnostics[0].offset, Some(16));
+        assert_eq!(diagnostics[0].end_offset, Some(22));
+
+        assert!(check_with(&LineLength::new(16), content).is_empty());
+        assert!(check_with(&LineLength::new(13), "X = äöüäöüäöü\n").is_empty());
+
+        let mut rule = LineLength::new(16);
+        let options = HashMap::from([("display_width".to_string(), toml::Value::Boolean(false))]);
+        rule.configure(&options).unwrap();
+        assert_eq!(
+            check_with(&rule, content)[0].message,
+            "Line length 22 exceeds maximum of 16"
+        );
+    }
+
+    #[test]
+    fn test_naming_rules_report_name_span() {
+        let diagnostics = check_with(&VariableNaming::new(NamingStyle::Upper), "  cflags = -O2\n");
+        assert_eq!(diagnostics[0].column, 3);
+        assert_eq!(diagnostics[0].end_column, Some(9));
+
+        let diagnostics = check_with(&TargetNaming::new(NamingStyle::Lower), "all Build: app\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].column, 5);
+        assert_eq!(diagnostics[0].end_column, Some(10));
+    }
+
+    #[test]
+    fn test_implicit_default_goal() {
+        let explicit = ".DEFAULT_GOAL := build\n\nclean:\n\trm -f app\n\nbuild:\n\tcc main.c\n";
+        assert!(check_with(&ImplicitDefaultGoal, explicit).is_empty());
+
+        let implicit = "# Build\nclean:\n\trm -f app\n\nall: app\n";
+        let diagnostics = check_with(&ImplicitDefaultGoal, implicit);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("'clean'"));
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+
+        let starts_with_all = ".PHONY: all\nall: app\n\napp: main.c\n\tcc -o $@ $<\n";
+        assert!(check_with(&ImplicitDefaultGoal, starts_with_all).is_empty());
+    }
+
+    #[test]
+    fn test_tab_outside_recipe() {
+        let content = "CC = gcc\n\n\techo orphan\n\nbuild:\n\t$(CC) main.c\n";
+        let diagnostics = check_with(&TabOutsideRecipe, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+
+        let define = "define COMPILE\n\t$(CC) -c $<\nendef\n";
+        assert!(check_with(&TabOutsideRecipe, define).is_empty());
+
+        let conditional = "ifeq ($(OS),Windows_NT)\n\tEXE = .exe\nendif\n\nbuild:\nifdef DEBUG\n\techo debug\nendif\n\tcc main.c\n";
+        assert!(check_with(&TabOutsideRecipe, conditional).is_empty());
+    }
+
+    #[test]
+    fn test_tab_outside_recipe_accepts_dotted_rules() {
+        let suffix = ".SUFFIXES: .c .o
+.c.o:
+	$(CC) -c $<
+";
+        assert!(check_with(&TabOutsideRecipe, suffix).is_empty());
+
+        let default = ".DEFAULT:
+	@echo no rule for $@
+";
+        assert!(check_with(&TabOutsideRecipe, default).is_empty());
+
+        // A dotted assignment doesn't start a recipe
+        let assignment = ".RECIPEPREFIX := >
+	echo orphan
+";
+        assert_eq!(check_with(&TabOutsideRecipe, assignment).len(), 1);
+    }
+
+    #[test]
+    fn test_shell_without_flags() {
+        let without_flags = "SHELL := /bin/bash\n\nbuild:\n\tcc main.c | tee log\n";
+        let diagnostics = check_with(&ShellWithoutFlags, without_flags);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+
+        let with_flags = "SHELL := /bin/bash\n.SHELLFLAGS := -eu -o pipefail -c\n";
+        assert!(check_with(&ShellWithoutFlags, with_flags).is_empty());
+
+        assert!(check_with(&ShellWithoutFlags, "SHELL = /bin/sh\n").is_empty());
+    }
+
+    #[test]
+    fn test_echo_prefix_consistency_flags_minority() {
+        let content = "build:\n\t@echo Building\n\tcc main.c\n\t@echo Linking\n\techo Done\n\t@printf 'ok\\n'\n";
+        let diagnostics = check_with(&EchoPrefixConsistency::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (5, 2));
+        assert!(fix_with(&EchoPrefixConsistency::new(), content).contains("\t@echo Done\n"));
+    }
+
+    #[test]
+    fn test_echo_prefix_consistency_options() {
+        let content = "build:\n\t@echo Building\n\t@echo Linking\n\techo Done\n";
+
+        let mut strict = EchoPrefixConsistency::new();
+        let options = HashMap::from([("threshold".to_string(), toml::Value::Float(0.7))]);
+        strict.configure(&options).unwrap();
+        assert!(check_with(&strict, content).is_empty());
+
+        let mut verbose = EchoPrefixConsistency::new();
+        let options = HashMap::from([(
+            "prefer".to_string(),
+            toml::Value::String("verbose".to_string()),
+        )]);
+        verbose.configure(&options).unwrap();
+        assert_eq!(check_with(&verbose, content).len(), 2);
+        assert_eq!(
+            fix_with(&verbose, content),
+            "build:\n\techo Building\n\techo Linking\n\techo Done\n"
+        );
+    }
+
+    #[test]
+    fn test_hardcoded_path_reports_path_column() {
+        let content = "install:\n\t@cp app /usr/local/bin/app\n\tcp lib.so \\\n\t  /opt/lib\n";
+        let diagnostics = check_with(&HardcodedPath, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 10));
+        assert_eq!(diagnostics[0].end_column, Some(28));
+        assert!(diagnostics[0].message.contains("'/usr/local/bin/app'"));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (4, 4));
+    }
+
+    #[test]
+    fn test_automatic_variable_assignment() {
+        let content = "@ = x\nMAKEFILE_LIST += extra.mk\n";
+        let diagnostics = check_with(&AutomaticVariableAssignment, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].rule_id, "MK005");
+        assert_eq!(diagnostics[0].line, 1);
+        assert_eq!(diagnostics[1].line, 2);
+
+        assert!(check_with(&AutomaticVariableAssignment, "FOO = x\n").is_empty());
+    }
+
+    #[test]
+    fn test_tool_variable_override() {
+        let content = "CC = gcc\nCFLAGS := -O2\n";
+        let diagnostics = check_with(&ToolVariableOverride::new(), content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 4));
+        // `?=` would never replace Make's own default for CC
+        assert!(diagnostics[0]
+            .message
+            .contains("ifeq ($(origin CC),default)"));
+        assert!(!diagnostics[0].fixable);
+        assert!(diagnostics[1].fixable);
+        assert_eq!(
+            fix_with(&ToolVariableOverride::new(), content),
+            "CC = gcc\nCFLAGS ?= -O2\n"
+        );
+
+        assert!(check_with(&ToolVariableOverride::new(), "CC ?= gcc\nCFLAGS += -g\n").is_empty());
+        assert!(check_with(
+            &ToolVariableOverride::new(),
+            "ifeq ($(origin CC),default)\nCC = gcc\nendif\n"
+        )
+        .is_empty());
+
+        let mut custom = ToolVariableOverride::new();
+        let options = HashMap::from([(
+            "variables".to_string(),
+            toml::Value::Array(vec![toml::Value::String("GO".to_string())]),
+        )]);
+        custom.configure(&options).unwrap();
+        assert!(check_with(&custom, content).is_empty());
+        assert_eq!(check_with(&custom, "GO = go1.22\n").len(), 1);
+    }
+
+    #[test]
+    fn test_bashism_under_default_shell() {
+        let content =
+            "check:\n\t@if [[ -f x ]]; then echo yes; fi\n\tgrep foo <<< \"$$DATA\"\n\techo [ok]\n";
+        let diagnostics = check_with(&Bashism, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 6));
+        assert_eq!(diagnostics[1].line, 3);
+
+        let bash = format!("SHELL := /bin/bash\n{content}");
+        assert!(check_with(&Bashism, &bash).is_empty());
+
+        let sh = format!("SHELL = /bin/sh\n{content}");
+        assert_eq!(check_with(&Bashism, &sh).len(), 2);
+
+        let commands = "setup:\n\tsource env.sh\n\t@function greet { echo hi; }\n";
+        let diagnostics = check_with(&Bashism, commands);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 2));
+        assert!(diagnostics[0].message.contains("'source'"));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (3, 3));
+        assert!(diagnostics[1].message.contains("'function'"));
+    }
+
+    #[test]
+    fn test_invalid_variable_syntax_fix_renames_references() {
+        let content = "MY VAR = 1\n\nall:\n\techo $(MY VAR) ${MY VAR}\n";
+        let diagnostics = check_with(&InvalidVariableSyntax::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(!diagnostics[0].fix.as_ref().unwrap().safe);
+        assert_eq!(
+            fix_with(&InvalidVariableSyntax::new(), content),
+            "MY_VAR = 1\n\nall:\n\techo $(MY_VAR) ${MY_VAR}\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_variable_syntax_fix_replaces_dollar() {
+        let content = "FOO$ = 1\nBAR = $(FOO$)\n";
+
+        let mut rule = InvalidVariableSyntax::new();
+        let options =
+            HashMap::from([("rename_references".to_string(), toml::Value::Boolean(false))]);
+        rule.configure(&options).unwrap();
+
+        assert_eq!(fix_with(&rule, content), "FOO_ = 1\nBAR = $(FOO$)\n");
+        assert_eq!(
+            fix_with(&InvalidVariableSyntax::new(), content),
+            "FOO_ = 1\nBAR = $(FOO_)\n"
+        );
+    }
+
+    #[test]
+    fn test_silent_target_suggests_silent_declaration() {
+        let content = "all: build\n\nbuild:\n\t@echo Building\n\t-@cc main.c\n\ntest:\n\t@echo Testing\n\t./run-tests\n";
+        let diagnostics = check_with(&SilentTarget::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+        assert!(diagnostics[0].message.contains("'build'"));
+
+        let declared = format!(".SILENT: build\n{content}");
+        assert!(check_with(&SilentTarget::new(), &declared).is_empty());
+
+        let mut prefix = SilentTarget::new();
+        let options = HashMap::from([(
+            "prefer".to_string(),
+            toml::Value::String("prefix".to_string()),
+        )]);
+        prefix.configure(&options).unwrap();
+        assert!(check_with(&prefix, content).is_empty());
+        assert_eq!(check_with(&prefix, &declared).len(), 1);
+    }
+
+    #[test]
+    fn test_undefined_prerequisite() {
+        let content = ".PHONY: all test\nall: build tset main.o\n\nbuild: lib-%.a\n\tcc main.c\n\nlib-%.a:\n\tar rcs $@\n";
+        let diagnostics = check_with(&UndefinedPrerequisite::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 12));
+        assert!(diagnostics[0].message.contains("'tset'"));
+
+        let mut check_fs = UndefinedPrerequisite::new();
+        let options = HashMap::from([("check_fs".to_string(), toml::Value::Boolean(true))]);
+        check_fs.configure(&options).unwrap();
+        let diagnostics = check_with(&check_fs, "all: Cargo.toml missing.txt\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("'missing.txt'"));
+    }
+
+    #[test]
+    fn test_permissive_chmod() {
+        let diagnostics = check_with(
+            &PermissiveChmod,
+            "app:\n\tcc -o $@ main.c\n\tchmod 777 $@\n",
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+        assert!(diagnostics[0].message.contains("'777'"));
+
+        assert_eq!(
+            check_with(&PermissiveChmod, "app:\n\tchmod -R a+rwx out\n").len(),
+            1
+        );
+        assert!(check_with(&PermissiveChmod, "app:\n\tchmod 644 $@\n").is_empty());
+        assert!(check_with(&PermissiveChmod, "app:\n\tchmod u+x,go-w $@\n").is_empty());
+    }
+
+    #[test]
+    fn test_export_undefined() {
+        assert!(check_with(&ExportUndefined, "export FOO\nFOO = 1\n").is_empty());
+        assert!(check_with(&ExportUndefined, "export BAR = 2\nexport PATH\n").is_empty());
+
+        let diagnostics = check_with(&ExportUndefined, "FOO = 1\nexport FOO BAZ\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 12));
+        assert!(diagnostics[0].message.contains("'BAZ'"));
+    }
+
+    #[test]
+    fn test_hardcoded_path_in_variable_offers_prefix_fix() {
+        let content = "BINDIR =  /usr/local/bin\nDATA := $(HOME) /srv/data\n";
+        let diagnostics = check_with(&HardcodedPath, content);
+        assert_eq!(diagnostics.len(), 2);
+
+        let bindir = diagnostics.iter().find(|d| d.line == 1).unwrap();
+        assert_eq!((bindir.column, bindir.end_column), (11, Some(25)));
+        let edit = &bindir.fix.as_ref().unwrap().edits[0];
+        assert_eq!((edit.start_column, edit.end_column), (11, 25));
+        assert_eq!(edit.replacement, "$(PREFIX)/bin");
+
+        // Paths outside /usr/local aren't installation paths
+        assert!(diagnostics
+            .iter()
+            .find(|d| d.line == 2)
+            .unwrap()
+            .fix
+            .is_none());
+        assert_eq!(
+            fix_with(&HardcodedPath, content),
+            "BINDIR =  $(PREFIX)/bin\nDATA := $(HOME) /srv/data\n"
+        );
+
+        let shell = check_with(&HardcodedPath, "SHELL = /bin/sh\n");
+        assert_eq!(shell.len(), 1);
+        assert!(shell[0].fix.is_none());
+        // Rewriting PREFIX, or what it is defined from, in terms of PREFIX makes it recursive
+        assert!(check_with(&HardcodedPath, "PREFIX ?= /usr/local\n").is_empty());
+        let content = "ROOT = /usr/local\nPREFIX = $(ROOT)\nBINDIR = /usr/local/bin\n";
+        let diagnostics = check_with(&HardcodedPath, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].fix.is_none());
+        assert!(diagnostics[1].fix.is_some());
+    }
+
+    #[test]
+    fn test_blank_line_in_recipe() {
+        let content = "build:\n\tcc -c main.c\n\n\tcc -o app main.o\n\nclean:\n\trm -f app\n";
+        let diagnostics = check_with(&BlankLineInRecipe, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+        assert!(diagnostics[0].message.contains("'build'"));
+        assert_eq!(
+            fix_with(&BlankLineInRecipe, content),
+            "build:\n\tcc -c main.c\n\tcc -o app main.o\n\nclean:\n\trm -f app\n"
+        );
+    }
+
+    #[test]
+    fn test_multiple_phony_declarations() {
+        let content = ".PHONY: build\nbuild:\n\tcc main.c\n\n.PHONY: test\ntest:\n\t./run\n";
+        let diagnostics = check_with(&MultiplePhony, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (5, 1));
+        assert!(diagnostics[0].message.contains("line 1"));
+
+        assert!(check_with(&MultiplePhony, ".PHONY: build test\nbuild:\n").is_empty());
+    }
+
+    #[test]
+    fn test_unused_variable() {
+        let content = "\
+FOO = unused
+BAR = used
+OBJS = main.o
+SRCS = ${OBJS:.o=.c}
+CFLAGS = -O2
+export TOKEN = secret
+ifdef DEBUG_FLAGS
+endif
+DEBUG_FLAGS = -g
+
+app: $(SRCS)
+\tcc $(BAR) -o $@ $^
+";
+        let diagnostics = check_with(&UnusedVariable, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+        assert!(diagnostics[0].message.contains("'FOO'"));
+
+        let computed = "FOO_FLAGS = -x\nNAME = FOO\nall:\n\techo $($(NAME)_FLAGS)\n";
+        assert!(check_with(&UnusedVariable, computed).is_empty());
+    }
+
+    #[test]
+    fn test_eager_shell() {
+        let content = "REV := $(shell git rev-parse HEAD)\nDATE = $(shell git log -1)\nOS := $(shell uname)\n";
+        let diagnostics = check_with(&EagerShell::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 8));
+        assert!(diagnostics[0].message.contains("`git`"));
+
+        let mut custom = EagerShell::new();
+        let options = HashMap::from([(
+            "commands".to_string(),
+            toml::Value::Array(vec![toml::Value::String("uname".to_string())]),
+        )]);
+        custom.configure(&options).unwrap();
+        let diagnostics = check_with(&custom, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+    }
+
+    #[test]
+    fn test_ignored_error() {
+        let content = "build:\n\t-gcc -o app main.c\n\t@-install app /usr/bin\n\nclean:\n\t-rm -f x\n\t-$(RM) y\n";
+        let diagnostics = check_with(&IgnoredError::new(), content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 2));
+        assert!(diagnostics[0].message.contains("'gcc'"));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (3, 3));
+
+        assert_eq!(
+            fix_with(&IgnoredError::new(), content),
+            "build:\n\tgcc -o app main.c\n\t@install app /usr/bin\n\nclean:\n\t-rm -f x\n\t-$(RM) y\n"
+        );
+    }
+
+    #[test]
+    fn test_dangling_continuation() {
+        let content = "all:\n\techo hi\nFOO = bar \\\n";
+        let diagnostics = check_with(&DanglingContinuation, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, 11));
+        assert_eq!(
+            fix_with(&DanglingContinuation, content),
+            "all:\n\techo hi\nFOO = bar\n"
+        );
+
+        assert_eq!(
+            check_with(&DanglingContinuation, "FOO = a \\\n\n\n").len(),
+            1
+        );
+        assert!(check_with(&DanglingContinuation, "FOO = a \\\n  b\n").is_empty());
+        assert!(check_with(&DanglingContinuation, "FOO = a\\\\\n").is_empty());
+    }
+
+    #[test]
+    fn test_required_include() {
+        let diagnostics = check_with(&RequiredInclude, "include config.mk\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Info);
+        assert_eq!(
+            fix_with(&RequiredInclude, "include config.mk\n"),
+            "-include config.mk\n"
+        );
+
+        let diagnostics = check_with(&RequiredInclude, "include $(OBJS:.o=.d)\n");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+
+        assert!(check_with(&RequiredInclude, "-include config.mk\n").is_empty());
+        assert!(check_with(&RequiredInclude, "sinclude config.mk\n").is_empty());
+        assert!(check_with(&RequiredInclude, "include /etc/make.conf\n").is_empty());
+    }
+
+    #[test]
+    fn test_long_prerequisite_list() {
+        let content = "all: a b c d e f g h i j\n\t@echo done\n";
+        let diagnostics = check_with(&LongPrerequisiteList::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+        assert!(diagnostics[0].message.starts_with("10 prerequisites"));
+
+        let wrapped = "all: a b c d e \\\n\tf g h i j\n";
+        assert!(check_with(&LongPrerequisiteList::new(), wrapped).is_empty());
+        assert!(check_with(
+            &LongPrerequisiteList::new(),
+            "all: a b | c d e f g h ; @true\n"
+        )
+        .is_empty());
+
+        let mut rule = LongPrerequisiteList::new();
+        let options = HashMap::from([("max".to_string(), toml::Value::Integer(12))]);
+        rule.configure(&options).unwrap();
+        assert!(check_with(&rule, content).is_empty());
+    }
+
+    #[test]
+    fn test_doubled_operator() {
+        let diagnostics = check_with(&DoubledOperator, "FOO == bar\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 5));
+        assert!(diagnostics[0].message.contains("did you mean '='"));
+        assert_eq!(fix_with(&DoubledOperator, "FOO == bar\n"), "FOO = bar\n");
+
+        let diagnostics = check_with(&DoubledOperator, "FOO :== bar\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("did you mean ':='"));
+        assert_eq!(fix_with(&DoubledOperator, "FOO :== bar\n"), "FOO := bar\n");
+
+        assert_eq!(fix_with(&DoubledOperator, "FOO ==   bar\n"), "FOO = bar\n");
+        assert_eq!(fix_with(&DoubledOperator, "FOO ==bar\n"), "FOO = bar\n");
+
+        assert!(check_with(&DoubledOperator, "FOO = =bar\n").is_empty());
+        assert!(check_with(&DoubledOperator, "FOO = bar == baz\n").is_empty());
+    }
+
+    #[test]
+    fn test_sudo() {
+        let diagnostics = check_with(&Sudo, "install:\n\tsudo make install\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 1));
+        assert!(diagnostics[0].message.contains("'install'"));
+        assert_eq!(
+            check_with(
+                &Sudo,
+                "deps:\n\tapt-get update && sudo apt-get install -y gcc\n"
+            )
+            .len(),
+            1
+        );
+
+        assert!(check_with(&Sudo, "install:\n\tmake install\n").is_empty());
+        assert!(check_with(&Sudo, "install:\n\t# sudo make install\n").is_empty());
+        assert!(check_with(&Sudo, "install:\n\tcp app $(DESTDIR) # sudo if needed\n").is_empty());
+        assert!(check_with(&Sudo, "check:\n\t./pseudo-sudoku\n").is_empty());
+    }
+
+    #[test]
+    fn test_undocumented_phony() {
+        let content = "\
+.PHONY: build test
+## Build the app
+build:
+\t@cargo build
+
+test:
+\t@cargo test
+
+out.txt:
+\t@touch $@
+";
+        let diagnostics = check_with(&UndocumentedPhony, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (6, 1));
+        assert!(diagnostics[0].message.contains("'test'"));
+    }
+
+    #[test]
+    fn test_recursive_self_reference() {
+        let diagnostics = check_with(&RecursiveSelfReference, "FOO = $(FOO) x\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+        assert_eq!(
+            fix_with(&RecursiveSelfReference, "FOO = $(FOO) x # extra\n"),
+            "FOO += x # extra\n"
+        );
+
+        assert_eq!(
+            check_with(&RecursiveSelfReference, "OBJS = $(OBJS:.c=.o)\n").len(),
+            1
+        );
+        assert_eq!(
+            check_with(&RecursiveSelfReference, "FOO = a ${FOO}\n").len(),
+            1
+        );
+        assert!(check_with(&RecursiveSelfReference, "FOO := $(FOO) x\n").is_empty());
+        assert!(check_with(&RecursiveSelfReference, "FOO += $(FOO) x\n").is_empty());
+        assert!(check_with(&RecursiveSelfReference, "FOO = $(FOOBAR) x\n").is_empty());
+    }
+
+    #[test]
+    fn test_rule_options_describe_defaults() {
+        let options = LineLength::new(120).options();
+        assert_eq!(options.len(), 2);
+        assert_eq!(options[0].name, "max");
+        assert_eq!(options[0].kind, OptionKind::Integer);
+        assert_eq!(options[0].default, toml::Value::Integer(120));
+
+        let naming = VariableNaming::new(NamingStyle::Upper);
+        assert_eq!(naming.options()[0].name, "style");
+        assert_eq!(naming.options()[0].default, toml::Value::from("UPPER_CASE"));
+    }
+
+    #[test]
+    fn test_naming_style_option() {
+        let mut rule = TargetNaming::new(NamingStyle::Lower);
+        let options = HashMap::from([("style".to_string(), toml::Value::from("UPPER_CASE"))]);
+        rule.configure(&options).unwrap();
+        assert_eq!(check_with(&rule, "build:\n\t@true\n").len(), 1);
+        assert!(check_with(&rule, "BUILD:\n\t@true\n").is_empty());
+
+        let options = HashMap::from([("style".to_string(), toml::Value::from("camel"))]);
+        assert!(rule.configure(&options).is_err());
+    }
+
+    #[test]
+    fn test_embedded_tab() {
+        let content = "all: a\tb\n\t@echo $@\nCC =\t\tgcc # \taligned\n";
+        let diagnostics = check_with(&EmbeddedTab, content);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 7));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (3, 5));
+        assert_eq!(
+            fix_with(&EmbeddedTab, content),
+            "all: a b\n\t@echo $@\nCC = gcc # \taligned\n"
+        );
+
+        assert!(check_with(&EmbeddedTab, "all:\n\t@echo\thi\n").is_empty());
+        assert!(check_with(&EmbeddedTab, "define CMD\n\t@echo hi\nendef\n").is_empty());
+    }
+
+    #[test]
+    fn test_hardcoded_path_skips_variable_driven_words() {
+        let content = "\
+LIBDIR = $(PREFIX)/lib
+BINDIR = ${HOME}/bin
+DIRS = $(addsuffix /lib,$(ROOTS))
+SYSLIB = /usr/local/lib
+install:
+\tinstall -d $(DESTDIR)/usr/share
+";
+        let diagnostics = check_with(&HardcodedPath, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 4);
+        assert!(diagnostics[0].message.contains("'/usr/local/lib'"));
+    }
+
+    #[test]
+    fn test_missing_final_newline() {
+        let diagnostics = check_with(&MissingFinalNewline, "all:\n\t@echo hi");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 10));
+        assert_eq!(
+            fix_with(&MissingFinalNewline, "all:\n\t@echo hi"),
+            "all:\n\t@echo hi\n"
+        );
+        assert_eq!(
+            fix_with(&MissingFinalNewline, "all:\r\n\t@echo hi"),
+            "all:\r\n\t@echo hi\r\n"
+        );
+
+        assert!(check_with(&MissingFinalNewline, "all:\n\t@echo hi\n").is_empty());
+        assert!(check_with(&MissingFinalNewline, "").is_empty());
+    }
+
+    #[test]
+    fn test_snake_and_kebab_naming_styles() {
+        let variables = "BUILD_ALL = 1\nBUILD-ALL = 1\nbuild_all = 1\n";
+        let flagged = |style| {
+            let mut lines: Vec<usize> = check_with(&VariableNaming::new(style), variables)
+                .iter()
+                .map(|d| d.line)
+                .collect();
+            lines.sort();
+            lines
+        };
+        assert_eq!(flagged(NamingStyle::SnakeUpper), [2, 3]);
+        assert_eq!(flagged(NamingStyle::SnakeLower), [1, 2]);
+
+        let targets = "build-all:\n\t@true\nbuild_all:\n\t@true\nout/app.o:\n\t@true\n";
+        let diagnostics = check_with(&TargetNaming::new(NamingStyle::Kebab), targets);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+
+        let mut rule = TargetNaming::new(NamingStyle::Lower);
+        let options = HashMap::from([("style".to_string(), toml::Value::from("kebab-case"))]);
+        rule.configure(&options).unwrap();
+        assert_eq!(check_with(&rule, targets).len(), 1);
+    }
+
+    #[test]
+    fn test_long_recipe_command() {
+        let flags = "-Wall ".repeat(80);
+        let content = format!("app: main.c\n\tgcc \\\n\t\t{flags}\\\n\t\t-o app ../main.c\n");
+        let diagnostics = check_with(&LongRecipeCommand::new(), &content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 1));
+        assert!(diagnostics[0]
+            .message
+            .starts_with("Recipe command is 500 characters"));
+
+        assert!(check_with(
+            &LongRecipeCommand::new(),
+            "app: main.c\n\tgcc -o app main.c\n"
+        )
+        .is_empty());
+
+        let mut rule = LongRecipeCommand::new();
+        let options = HashMap::from([("max".to_string(), toml::Value::Integer(500))]);
+        rule.configure(&options).unwrap();
+        assert!(check_with(&rule, &content).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_special_target() {
+        let content = ".PHNOY: all\n.SECONDARY: gen.c\n.PRECIOUS: %.o\n.BUILD:\nall:\n\t@true\n";
+        let mut diagnostics = check_with(&UnknownSpecialTarget, content);
+        diagnostics.sort_by_key(|d| d.line);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+        assert_eq!(diagnostics[0].end_column, Some(7));
+        assert!(diagnostics[0].message.contains("did you mean '.PHONY'?"));
+        assert_eq!(diagnostics[1].message, "Unknown special target '.BUILD'");
+
+        assert!(check_with(&UnknownSpecialTarget, ".venv:\n\tpython -m venv .venv\n").is_empty());
+    }
+
+    #[test]
+    fn test_echo_progress() {
+        let content = "build:\n\t@echo \"building\"\n\t@echo \"$$x\" > f\n\t@echo -n done\n\techo linking $(APP)\n";
+        let diagnostics = check_with(&EchoProgress::new(), content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 1));
+        assert!(diagnostics[0].message.contains("'$(info building)'"));
+
+        let mut rule = EchoProgress::new();
+        let options = HashMap::from([("silent_only".to_string(), toml::Value::Boolean(false))]);
+        rule.configure(&options).unwrap();
+        let lines: Vec<usize> = check_with(&rule, content).iter().map(|d| d.line).collect();
+        assert_eq!(lines, [2, 5]);
+
+        assert!(EchoProgress::new().opt_in());
+    }
+
+    #[test]
+    fn test_missing_output_directory() {
+        let diagnostics = check_with(
+            &MissingOutputDirectory,
+            "build/app: main.c\n\tcc -o $@ $<\n",
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+        assert!(diagnostics[0].message.contains("doesn't create 'build'"));
+
+        for content in [
+            "build/app: main.c\n\tmkdir -p build\n\tcc -o $@ $<\n",
+            "build/app: main.c | build\n\tcc -o $@ $<\n",
+            "src/main.o: src/main.c\n\tcc -c -o $@ $<\n",
+            "build/stamp:\n",
+        ] {
+            assert!(
+                check_with(&MissingOutputDirectory, content).is_empty(),
+                "{content}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_gnu_function() {
+        let content = "ROOT := $(abspath x)\nSRCS = $(wildcard *.c)\n";
+        assert!(check_with(&GnuFunction::new(), content).is_empty());
+
+        let mut rule = GnuFunction::new();
+        let options = HashMap::from([("portable".to_string(), toml::Value::Boolean(true))]);
+        rule.configure(&options).unwrap();
+        let diagnostics = check_with(&rule, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 9));
+        assert!(diagnostics[0]
+            .message
+            .starts_with("'$(abspath)' is specific to GNU Make"));
+
+        let posix = format!(".POSIX:\n{content}");
+        assert_eq!(check_with(&GnuFunction::new(), &posix).len(), 1);
+    }
+
+    #[test]
+    fn test_misplaced_dependency() {
+        let content = "app: main.o\n\tcc -o app main.o\napp: util.o\n\ninstall: app\n\tcp app /usr/local/bin\ninstall: docs\n\tcp -r docs /usr/local/share\n";
+        let diagnostics = check_with(&MisplacedDependency, content);
+        let found: Vec<_> = diagnostics.iter().map(|d| (d.line, d.severity)).collect();
+        assert_eq!(found, [(3, Severity::Info), (7, Severity::Warning)]);
+        assert!(diagnostics[0]
+            .message
+            .contains("after its recipe on line 1"));
+
+        // Only different branches of the same conditional are exclusive
+        let content = "app: main.c\n\tcc -o app main.c\nifdef DEBUG\napp: debug.c\nendif\n";
+        let diagnostics = check_with(&MisplacedDependency, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 4);
+
+        for content in [
+            "app: util.o\napp: main.o\n\tcc -o app main.o util.o\n",
+            "clean::\n\trm -f a\nclean::\n\trm -f b\n",
+            "%.o: %.c\n\tcc -c $<\n%.o: %.s\n\tas -o $@ $<\n",
+            "ifeq ($(OS),Windows_NT)\napp: main.c\n\tcl main.c\nelse\napp: main.c\n\tcc -o app main.c\nendif\n",
+        ] {
+            assert!(
+                check_with(&MisplacedDependency, content).is_empty(),
+                "{content}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_shadowed_variable() {
+        let content = "CC = gcc\nCFLAGS := -O2\nCC = clang\n";
+        let diagnostics = check_with(&ShadowedVariable, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (3, 1));
+        assert_eq!(
+            diagnostics[0].message,
+            "'CC' is assigned again before the value from line 1 is used"
+        );
+
+        for content in [
+            "CFLAGS = -O2\nCFLAGS += -g\n",
+            "CC = gcc\nCC ?= clang\n",
+            "FLAGS := -O2\nFLAGS := $(FLAGS) -g\n",
+            "OUT = build\nDIR := $(OUT)/bin\nOUT = dist\n",
+            "ifeq ($(OS),Windows_NT)\nEXE = app.exe\nelse\nEXE = app\nendif\n",
+        ] {
+            assert!(
+                check_with(&ShadowedVariable, content).is_empty(),
+                "{content}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().join("Makefile");
+        let content = "include common.mk\nall:\n\t@true\n";
+        std::fs::write(&root, content).unwrap();
+        std::fs::write(dir.path().join("common.mk"), "include Makefile\n").unwrap();
+
+        let mut makefile = parse(content).unwrap();
+        makefile.path = Some(root);
+        assert!(IncludeCycle::new().check(&makefile, content).is_empty());
+
+        let mut rule = IncludeCycle::new();
+        let options = HashMap::from([("follow_includes".to_string(), toml::Value::Boolean(true))]);
+        rule.configure(&options).unwrap();
+        let diagnostics = rule.check(&makefile, content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+        assert_eq!(
+            diagnostics[0].message,
+            "Include cycle: Makefile -> common.mk -> Makefile"
+        );
+
+        std::fs::write(dir.path().join("common.mk"), "CC = cc\n").unwrap();
+        assert!(rule.check(&makefile, content).is_empty());
+
+        // Paths in included files are resolved from the root's directory, like Make does
+        std::fs::create_dir(dir.path().join("mk")).unwrap();
+        std::fs::write(dir.path().join("mk/rules.mk"), "include Makefile\n").unwrap();
+        let content = "include mk/rules.mk\n";
+        let mut makefile = parse(content).unwrap();
+        makefile.path = Some(dir.path().join("Makefile"));
+        std::fs::write(dir.path().join("Makefile"), content).unwrap();
+        assert_eq!(rule.check(&makefile, content).len(), 1);
+    }
+}